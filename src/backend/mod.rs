@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use actix_web::error::BlockingError;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use actix_web::web;
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use futures::future::{self, Either};
+
+use crate::config::{ClusterConfig, SecretConfig};
+use crate::model::{ArrayValue, DecimalReturnType, Error, ErrorType, ExecuteStatementResponse, Field, LongReturnType, RecordsFormatType, SqlParameter, TypeHint, UpdateResult};
+
+mod mysql;
+mod postgres;
+mod sqlite;
+
+pub use self::mysql::MysqlBackend;
+pub use self::postgres::PostgresBackend;
+pub use self::sqlite::SqliteBackend;
+
+// a database engine the handlers can open connections on, as the user behind the request secret
+#[async_trait(?Send)]
+pub trait Backend: Send + Sync {
+    async fn connect(&self, secret: &SecretConfig, database: Option<String>, schema: Option<String>) -> Result<Box<dyn Connection>, Error>;
+}
+
+// how an ExecuteStatement request wants its statement run and its result shaped
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptions {
+    pub include_result_metadata: bool,
+    pub timeout: Duration,
+    pub continue_after_timeout: bool,
+    // bytes of records json, no limit when None
+    pub response_size_limit: Option<usize>,
+    pub format_records_as: RecordsFormatType,
+    pub decimal_return_type: DecimalReturnType,
+    pub long_return_type: LongReturnType,
+}
+
+// an open connection, either used for a single statement or held by a transaction
+#[async_trait(?Send)]
+pub trait Connection: Send {
+    async fn begin(&mut self) -> Result<(), Error>;
+    async fn select_database_and_schema(&mut self, database: Option<String>, schema: Option<String>) -> Result<(), Error>;
+    async fn execute(&mut self, sql: &str, parameters: Vec<SqlParameter>, options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error>;
+    async fn batch_execute(&mut self, sql: &str, parameter_sets: Vec<Vec<SqlParameter>>) -> Result<Vec<UpdateResult>, Error>;
+    async fn commit(&mut self) -> Result<(), Error>;
+    async fn rollback(&mut self) -> Result<(), Error>;
+    // false when the database ended the transaction by itself after a failed statement
+    fn is_in_transaction(&self) -> bool {
+        true
+    }
+}
+
+pub fn get_backend(cluster: &ClusterConfig) -> Arc<dyn Backend> {
+    match cluster.engine().as_str() {
+        "postgres" => Arc::new(PostgresBackend::new(cluster)),
+        "sqlite" => Arc::new(SqliteBackend::new(cluster)),
+        _ => Arc::new(MysqlBackend::new(cluster)),
+    }
+}
+
+// runs a synchronous driver call on the blocking thread pool, it keeps running when the caller stops waiting
+async fn blocking<T, F>(f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    web::block(f).await.map_err(|err| match err {
+        BlockingError::Error(err) => err,
+        BlockingError::Canceled => statement_canceled_error(),
+    })
+}
+
+fn statement_canceled_error() -> Error {
+    Error {
+        msg: "statement thread was canceled".to_owned(),
+        error_type: ErrorType::InternalServerErrorException,
+    }
+}
+
+// a driver call on the blocking thread pool with the connection locked, started is told once it has the lock
+async fn run_locked<C, T, F>(conn: &Arc<Mutex<C>>, started: Option<oneshot::Sender<()>>, f: F) -> Result<T, Error>
+where
+    C: Send + 'static,
+    F: FnOnce(&mut C) -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let conn = conn.clone();
+    blocking(move || {
+        let mut conn = lock_connection(&conn)?;
+        if let Some(started) = started {
+            let _ = started.send(());
+        }
+        f(&mut *conn)
+    }).await
+}
+
+// a statement that panicked while holding the connection poisons it, its state is unknown so it is not used again
+fn lock_connection<T>(conn: &Mutex<T>) -> Result<MutexGuard<'_, T>, Error> {
+    conn.lock().map_err(|_| Error {
+        msg: "The connection is no longer usable".to_owned(),
+        error_type: ErrorType::ServiceUnavailableError,
+    })
+}
+
+// the data api gives up on a statement after the timeout, without continueAfterTimeout the statement is cancelled too,
+// with it the statement finishes in the background and a transaction only sees its effects.
+// the timeout counts from started, a statement waiting for the one its transaction still runs neither times out nor cancels it
+async fn with_statement_timeout<T, S, C>(options: &ExecuteOptions, started: oneshot::Receiver<()>, statement: S, cancel: C) -> Result<T, Error>
+where
+    S: Future<Output = Result<T, Error>>,
+    C: Future<Output = Result<(), Error>>,
+{
+    let mut statement = Box::pin(statement);
+    if let Either::Right((result, _)) = future::select(started, statement.as_mut()).await {
+        return result;
+    }
+    match actix_rt::time::timeout(options.timeout, &mut statement).await {
+        Ok(result) => result,
+        Err(_) if options.continue_after_timeout => Err(statement_timeout_error()),
+        Err(_) => {
+            cancel.await?;
+            // the cancelled statement fails right away, wait for it so the connection is free again
+            let _ = statement.await;
+            Err(statement_timeout_error())
+        },
+    }
+}
+
+// the data api refuses results over 1 MiB, the size is counted while the rows are converted
+struct ResponseSize {
+    limit: Option<usize>,
+    size: usize,
+}
+
+impl ResponseSize {
+    fn new(options: &ExecuteOptions) -> ResponseSize {
+        ResponseSize {
+            limit: options.response_size_limit,
+            size: 0,
+        }
+    }
+    fn add(&mut self, record: &[Field]) -> Result<(), Error> {
+        // the comma between records included
+        self.size += serde_json::to_vec(record).map(|json| json.len() + 1).unwrap_or(0);
+        match self.limit {
+            Some(limit) if self.size > limit => Err(Error {
+                msg: "Database returned more than the allowed response size limit".to_owned(),
+                error_type: ErrorType::BadRequestException,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+// formatRecordsAs=JSON gives the rows as a json array of objects keyed by column label in formattedRecords instead of records,
+// statements without a result set have neither
+fn format_records(records: Vec<Vec<Field>>, labels: &[String], options: &ExecuteOptions) -> (Option<Vec<Vec<Field>>>, Option<String>) {
+    match options.format_records_as {
+        RecordsFormatType::None => (Some(records), None),
+        RecordsFormatType::Json if labels.is_empty() => (None, None),
+        RecordsFormatType::Json => {
+            // written by hand to keep the columns in select order
+            let rows: Vec<String> = records.iter()
+                .map(|record| {
+                    let columns: Vec<String> = labels.iter().zip(record.iter())
+                        .map(|(label, field)| format!("{}:{}", serde_json::Value::from(label.as_str()), field_to_json(field)))
+                        .collect();
+                    format!("{{{}}}", columns.join(","))
+                })
+                .collect();
+            (None, Some(format!("[{}]", rows.join(","))))
+        },
+    }
+}
+
+fn field_to_json(field: &Field) -> serde_json::Value {
+    match field {
+        Field::ArrayValue(value) => array_to_json(value),
+        Field::BlobValue(value) => serde_json::Value::from(value.as_str()),
+        Field::BooleanValue(value) => serde_json::Value::from(*value),
+        Field::DoubleValue(value) => serde_json::Value::from(*value),
+        Field::IsNull(_) => serde_json::Value::Null,
+        Field::LongValue(value) => serde_json::Value::from(*value),
+        Field::StringValue(value) => serde_json::Value::from(value.as_str()),
+        Field::StructValue(value) => serde_json::Value::Array(value.attributes.iter().map(field_to_json).collect()),
+    }
+}
+
+fn array_to_json(value: &ArrayValue) -> serde_json::Value {
+    match value {
+        ArrayValue::ArrayValues(values) => serde_json::Value::Array(values.iter().map(array_to_json).collect()),
+        ArrayValue::BooleanValues(values) => serde_json::Value::from(values.clone()),
+        ArrayValue::DoubleValues(values) => serde_json::Value::from(values.clone()),
+        ArrayValue::LongValues(values) => serde_json::Value::from(values.clone()),
+        ArrayValue::StringValues(values) => serde_json::Value::from(values.clone()),
+    }
+}
+
+// blobValue travels as base64 in both directions
+fn decode_blob(name: &str, value: &str) -> Result<Vec<u8>, Error> {
+    base64::decode(value).map_err(|_| Error {
+        msg: format!("Invalid blobValue for parameter {}: not a valid base64 value", name),
+        error_type: ErrorType::BadRequestException,
+    })
+}
+
+// the data api binds scalars only, kind is Array or Struct
+fn unsupported_parameter_error(kind: &str) -> Error {
+    Error {
+        msg: format!("{} parameters are not supported.", kind),
+        error_type: ErrorType::BadRequestException,
+    }
+}
+
+// a stringValue parameter checked against the format of its typeHint
+enum HintedValue {
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Timestamp(NaiveDateTime),
+    Decimal(String),
+    Json(serde_json::Value),
+    Uuid(String),
+}
+
+// the formats the data api accepts, anything else is rejected before it reaches the database
+fn parse_type_hint(name: &str, value: &str, type_hint: TypeHint) -> Result<HintedValue, Error> {
+    let (hinted_value, format) = match type_hint {
+        TypeHint::Date => (NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(HintedValue::Date), "YYYY-MM-DD"),
+        TypeHint::Time => (NaiveTime::parse_from_str(value, "%H:%M:%S%.f").ok().map(HintedValue::Time), "HH:MM:SS[.FFF]"),
+        TypeHint::Timestamp => (NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").ok().map(HintedValue::Timestamp), "YYYY-MM-DD HH:MM:SS[.FFF]"),
+        TypeHint::Decimal => (if is_decimal(value) { Some(HintedValue::Decimal(value.to_owned())) } else { None }, "a decimal number"),
+        TypeHint::Json => (serde_json::from_str(value).ok().map(HintedValue::Json), "a JSON document"),
+        TypeHint::Uuid => (if is_uuid(value) { Some(HintedValue::Uuid(value.to_lowercase())) } else { None }, "a UUID"),
+    };
+    match hinted_value {
+        Some(hinted_value) => Ok(hinted_value),
+        None => Err(Error {
+            msg: format!("Invalid value for parameter {} with typeHint {}: '{}' is not {}", name, type_hint, value, format),
+            error_type: ErrorType::BadRequestException,
+        }),
+    }
+}
+
+fn is_decimal(value: &str) -> bool {
+    let digits = value.strip_prefix('-').or(value.strip_prefix('+')).unwrap_or(value);
+    let mut parts = digits.splitn(2, '.');
+    let integer = parts.next().unwrap_or("");
+    let fraction = parts.next().unwrap_or("");
+    !(integer.is_empty() && fraction.is_empty())
+        && integer.chars().all(|ch| ch.is_ascii_digit())
+        && fraction.chars().all(|ch| ch.is_ascii_digit())
+}
+
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.iter().map(|group| group.len()).eq(vec![8, 4, 4, 4, 12])
+        && groups.iter().all(|group| group.chars().all(|ch| ch.is_ascii_hexdigit()))
+}
+
+// resultSetOptions for a DECIMAL/NUMERIC value the database gave as text
+fn decimal_field(value: String, decimal_return_type: DecimalReturnType) -> Field {
+    match decimal_return_type {
+        DecimalReturnType::String => Field::StringValue(value),
+        // DOUBLE_OR_LONG gives longValue without a fraction, doubleValue otherwise
+        DecimalReturnType::DoubleOrLong => match (value.contains('.'), value.parse::<i64>(), value.parse::<f64>()) {
+            (false, Ok(long_value), _) => Field::LongValue(long_value),
+            // a whole number too big for a long would lose digits as a double
+            (false, Err(_), _) => Field::StringValue(value),
+            (_, _, Ok(double_value)) if double_value.is_finite() => Field::DoubleValue(double_value),
+            _ => Field::StringValue(value),
+        },
+    }
+}
+
+// resultSetOptions for a BIGINT value
+fn long_field(value: i64, long_return_type: LongReturnType) -> Field {
+    match long_return_type {
+        LongReturnType::Long => Field::LongValue(value),
+        LongReturnType::String => Field::StringValue(value.to_string()),
+    }
+}
+
+fn statement_timeout_error() -> Error {
+    Error {
+        msg: "Request timed out".to_owned(),
+        error_type: ErrorType::StatementTimeoutException,
+    }
+}
+
+// replace every :name outside of quoted values with the placeholder of its index
+fn format_sql_params(sqlstr: String, placeholder: fn(usize) -> String) -> (String, HashMap<String, usize>){
+    let sqlvec: Vec<char> = sqlstr.chars().collect();
+    let mut targetsqlvec: Vec<char> = Vec::new();
+    let mut paramidxmap: HashMap<String, usize> = HashMap::new();
+    let mut value_quote = ' ';
+    let mut is_escape = false;
+    let mut is_param = false;
+    let mut paramidx = (0, 0);
+    for idx in 0..sqlvec.len() {
+        let ch = sqlvec[idx];
+        let mut proceed_with_paramname = false;
+        if is_escape {
+            is_escape = false;
+        } else if ch == '\\' {
+            is_escape = true;
+        } else if value_quote == ' ' && (ch == '\'' || ch == '\"') {
+            value_quote = ch;
+        } else if value_quote != ' ' && ch == value_quote {
+            if ch == '\'' && idx != sqlvec.len() - 1 && sqlvec[idx + 1] == '\'' {
+                is_escape = true;
+            } else {
+                value_quote = ' ';
+            }
+        } else {
+            proceed_with_paramname = true;
+        }
+        // println!("ch {}, value quote {} proceed_with_paramname {}", ch, value_quote, proceed_with_paramname);
+        if value_quote != ' ' || !proceed_with_paramname {
+            targetsqlvec.push(ch);
+            continue;
+        }
+        // a ':' right after another ':' is a postgres type cast such as `col::text`, not a param
+        if is_param && !(char::is_ascii_alphanumeric(&ch) || ch == '_') {
+            paramidx.1 = idx - 1;
+            is_param = false;
+            let param: String = sqlvec[(paramidx.0)..(paramidx.1 + 1)].iter().collect();
+            let next_idx = paramidxmap.len();
+            let st = placeholder(*paramidxmap.entry(param).or_insert(next_idx));
+            for pch in st.chars() {
+                targetsqlvec.push(pch);
+            }
+        } else if ch == ':' && idx != sqlvec.len() - 1 && char::is_ascii_alphanumeric(&sqlvec[idx+1]) && (idx == 0 || sqlvec[idx-1] != ':') {
+            is_param = true;
+            paramidx.0 = idx + 1;
+        }
+        if !is_param {
+            targetsqlvec.push(ch);
+        }
+    }
+    if is_param {
+        paramidx.1 = sqlvec.len() - 1;
+        let param: String = sqlvec[(paramidx.0)..(paramidx.1 + 1)].iter().collect();
+        let next_idx = paramidxmap.len();
+        let st = placeholder(*paramidxmap.entry(param).or_insert(next_idx));
+        for pch in st.chars() {
+            targetsqlvec.push(pch);
+        }
+    }
+    (targetsqlvec.into_iter().collect(), paramidxmap)
+}
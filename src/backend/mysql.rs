@@ -0,0 +1,724 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use core::hash::BuildHasherDefault;
+
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use chrono::{Datelike, Timelike};
+use mysql::{
+    error::{DriverError, Error as MysqlError},
+    consts::{ColumnType as MysqlColumnType, ColumnFlags as MysqlColumnFlags},
+    Column as MysqlColumn,
+    Value as MysqlValue,
+    Params as MysqlParams,
+};
+use encoding_rs::Encoding;
+use twox_hash::XxHash;
+
+use super::{blocking, decimal_field, decode_blob, format_records, format_sql_params, parse_type_hint, run_locked, unsupported_parameter_error, with_statement_timeout, Backend, Connection, ExecuteOptions, HintedValue, ResponseSize};
+use crate::config::{ClusterConfig, SecretConfig};
+use crate::model::{ColumnField, ColumnMetadata, DecimalReturnType, Error, ErrorType, ExecuteStatementResponse, LongReturnType, Field, MappedMysqlColumnType, SqlParameter, UpdateResult};
+
+pub struct MysqlBackend {
+    host: String,
+    port: u16,
+    database: String,
+}
+
+impl MysqlBackend {
+    pub fn new(cluster: &ClusterConfig) -> MysqlBackend {
+        MysqlBackend {
+            host: cluster.host(),
+            port: cluster.port(),
+            database: cluster.database(),
+        }
+    }
+    fn get_mysql_opts(&self, secret: &SecretConfig) -> mysql::Opts {
+        let mut opts = mysql::OptsBuilder::new();
+        opts.ip_or_hostname(Some(self.host.clone()))
+            .tcp_port(self.port)
+            .user(secret.user.clone())
+            .pass(secret.password.clone())
+            .db_name(Some(self.database.clone()))
+            // results come back as utf8mb4 whatever the column charset, like the data api json
+            .init(vec!["SET NAMES utf8mb4"]);
+        opts.into()
+    }
+}
+
+fn get_mysql_conn(opts: mysql::Opts) -> Result<mysql::Conn, Error> {
+    // a wrong password or missing grant comes back as MySqlError, same as a denied statement
+    match mysql::Conn::new(opts) {
+        Ok(conn) => Ok(conn),
+        // a refused or timed out connect is a driver error, a connection lost during the handshake an io error
+        Err(err @ MysqlError::IoError(_))
+        | Err(err @ MysqlError::DriverError(DriverError::CouldNotConnect(_)))
+        | Err(err @ MysqlError::DriverError(DriverError::ConnectTimeout)) => Err(Error {
+            msg: format!("Database is unavailable: {}", err),
+            error_type: ErrorType::DatabaseUnavailableException,
+        }),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[async_trait(?Send)]
+impl Backend for MysqlBackend {
+    async fn connect(&self, secret: &SecretConfig, database: Option<String>, schema: Option<String>) -> Result<Box<dyn Connection>, Error> {
+        let opts = self.get_mysql_opts(secret);
+        let connect_opts = opts.clone();
+        let (conn, connection_id) = blocking(move || {
+            let mut conn = get_mysql_conn(connect_opts)?;
+            select_database_and_schema(&mut conn, database, schema)?;
+            // the id KILL QUERY needs, mysql 17 does not expose the one from the handshake
+            let connection_id: Option<u32> = conn.first("SELECT CONNECTION_ID()")?;
+            Ok((conn, connection_id))
+        }).await?;
+        Ok(Box::new(MysqlConnection {
+            connection_id: connection_id.unwrap_or_default(),
+            conn: Arc::new(Mutex::new(conn)),
+            opts: opts,
+        }))
+    }
+}
+
+pub struct MysqlConnection {
+    // shared with the statement that is still running after a timeout with continueAfterTimeout
+    conn: Arc<Mutex<mysql::Conn>>,
+    // KILL QUERY needs a second connection as the same user
+    opts: mysql::Opts,
+    connection_id: u32,
+}
+
+impl MysqlConnection {
+    // the driver blocks, every call runs on the blocking thread pool and waits there for a statement still running
+    async fn run<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut mysql::Conn) -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        run_locked(&self.conn, None, f).await
+    }
+}
+
+#[async_trait(?Send)]
+impl Connection for MysqlConnection {
+    async fn begin(&mut self) -> Result<(), Error> {
+        self.run(|conn| {
+            conn.query("START TRANSACTION")?;
+            Ok(())
+        }).await
+    }
+    async fn select_database_and_schema(&mut self, database: Option<String>, schema: Option<String>) -> Result<(), Error> {
+        self.run(move |conn| select_database_and_schema(conn, database, schema)).await
+    }
+    async fn execute(&mut self, sql: &str, parameters: Vec<SqlParameter>, options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+        let sql = sql.to_owned();
+        let statement_options = options.clone();
+        let (started_tx, started) = oneshot::channel();
+        let statement = run_locked(&self.conn, Some(started_tx), move |conn| execute(conn, &sql, parameters, &statement_options));
+        let opts = self.opts.clone();
+        let connection_id = self.connection_id;
+        let cancel = blocking(move || {
+            get_mysql_conn(opts)?.query(format!("KILL QUERY {}", connection_id))?;
+            Ok(())
+        });
+        with_statement_timeout(options, started, statement, cancel).await
+    }
+    async fn batch_execute(&mut self, sql: &str, parameter_sets: Vec<Vec<SqlParameter>>) -> Result<Vec<UpdateResult>, Error> {
+        let sql = sql.to_owned();
+        self.run(move |conn| batch_execute(conn, &sql, parameter_sets)).await
+    }
+    async fn commit(&mut self) -> Result<(), Error> {
+        self.run(|conn| {
+            conn.query("COMMIT")?;
+            Ok(())
+        }).await
+    }
+    async fn rollback(&mut self) -> Result<(), Error> {
+        self.run(|conn| {
+            conn.query("Rollback")?;
+            Ok(())
+        }).await
+    }
+}
+
+// charset 63 is binary, numbers and dates have it too but only strings turn into BINARY/VARBINARY
+const BINARY_CHARSET: u16 = 63;
+
+fn map_mysql_column_type(column: &MysqlColumn, options: &ExecuteOptions) -> MappedMysqlColumnType {
+    let in_column_type = column.column_type();
+    let mut column_field = match in_column_type {
+        MysqlColumnType::MYSQL_TYPE_DECIMAL => ColumnField::DoubleValue,
+        MysqlColumnType::MYSQL_TYPE_TINY => ColumnField::LongValue,
+        MysqlColumnType::MYSQL_TYPE_SHORT => ColumnField::LongValue,
+        MysqlColumnType::MYSQL_TYPE_LONG => ColumnField::LongValue,
+        MysqlColumnType::MYSQL_TYPE_FLOAT => ColumnField::DoubleValue,
+        MysqlColumnType::MYSQL_TYPE_DOUBLE => ColumnField::DoubleValue,
+        MysqlColumnType::MYSQL_TYPE_NULL => ColumnField::IsNull,
+        MysqlColumnType::MYSQL_TYPE_TIMESTAMP => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_LONGLONG => ColumnField::LongValue,
+        MysqlColumnType::MYSQL_TYPE_INT24 => ColumnField::LongValue,
+        MysqlColumnType::MYSQL_TYPE_DATE => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_TIME => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_DATETIME => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_YEAR => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_NEWDATE => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_VARCHAR => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_BIT => ColumnField::BlobValue,
+        MysqlColumnType::MYSQL_TYPE_TIMESTAMP2 => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_DATETIME2 => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_TIME2 => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_JSON => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_NEWDECIMAL => ColumnField::DoubleValue,
+        MysqlColumnType::MYSQL_TYPE_ENUM => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_SET => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_TINY_BLOB => ColumnField::BlobValue,
+        MysqlColumnType::MYSQL_TYPE_MEDIUM_BLOB => ColumnField::BlobValue,
+        MysqlColumnType::MYSQL_TYPE_LONG_BLOB => ColumnField::BlobValue,
+        MysqlColumnType::MYSQL_TYPE_BLOB => ColumnField::BlobValue,
+        MysqlColumnType::MYSQL_TYPE_VAR_STRING => ColumnField::StringValue,
+        MysqlColumnType::MYSQL_TYPE_STRING => ColumnField::StringValue,
+        // the internal format, 4 bytes SRID and WKB, use ST_AsText in the sql for WKT
+        MysqlColumnType::MYSQL_TYPE_GEOMETRY => ColumnField::BlobValue,
+    };
+    // TEXT is sent as BLOB, the charset tells them apart, a _bin collation sets BINARY_FLAG on TEXT too
+    if column_field == ColumnField::BlobValue && column.character_set() != BINARY_CHARSET {
+        column_field = ColumnField::StringValue
+    }
+    match in_column_type {
+        MysqlColumnType::MYSQL_TYPE_VARCHAR | MysqlColumnType::MYSQL_TYPE_VAR_STRING | MysqlColumnType::MYSQL_TYPE_STRING if column.character_set() == BINARY_CHARSET => {
+            column_field = ColumnField::BlobValue
+        },
+        // like jdbc with tinyInt1isBit, only TINYINT(1) and BIT(1) are booleans, a wider BIT is its bytes
+        MysqlColumnType::MYSQL_TYPE_TINY | MysqlColumnType::MYSQL_TYPE_BIT if column.column_length() == 1 => {
+            column_field = ColumnField::BooleanValue
+        },
+        MysqlColumnType::MYSQL_TYPE_BIT | MysqlColumnType::MYSQL_TYPE_GEOMETRY => {
+            column_field = ColumnField::BlobValue
+        },
+        _ => {},
+    }
+    // resultSetOptions
+    match in_column_type {
+        MysqlColumnType::MYSQL_TYPE_DECIMAL | MysqlColumnType::MYSQL_TYPE_NEWDECIMAL => {
+            column_field = match options.decimal_return_type {
+                DecimalReturnType::String => ColumnField::StringValue,
+                DecimalReturnType::DoubleOrLong if column.decimals() == 0 => ColumnField::LongValue,
+                DecimalReturnType::DoubleOrLong => ColumnField::DoubleValue,
+            }
+        },
+        MysqlColumnType::MYSQL_TYPE_LONGLONG if options.long_return_type == LongReturnType::String => {
+            column_field = ColumnField::StringValue
+        },
+        _ => {},
+    }
+    MappedMysqlColumnType {
+        type_name: mysql_type_name(column),
+        column_field: column_field,
+        column_type: in_column_type,
+        character_set: column.character_set(),
+    }
+}
+// the java.sql.Types code the data api returns as columnMetadata.type
+fn mysql_jdbc_type(column: &MysqlColumn) -> i64 {
+    let binary = column.character_set() == BINARY_CHARSET;
+    match column.column_type() {
+        MysqlColumnType::MYSQL_TYPE_DECIMAL | MysqlColumnType::MYSQL_TYPE_NEWDECIMAL => 3,
+        MysqlColumnType::MYSQL_TYPE_TINY if column.column_length() == 1 => -7,
+        MysqlColumnType::MYSQL_TYPE_TINY => -6,
+        MysqlColumnType::MYSQL_TYPE_SHORT => 5,
+        MysqlColumnType::MYSQL_TYPE_LONG | MysqlColumnType::MYSQL_TYPE_INT24 => 4,
+        MysqlColumnType::MYSQL_TYPE_LONGLONG => -5,
+        MysqlColumnType::MYSQL_TYPE_FLOAT => 7,
+        MysqlColumnType::MYSQL_TYPE_DOUBLE => 8,
+        MysqlColumnType::MYSQL_TYPE_NULL => 0,
+        MysqlColumnType::MYSQL_TYPE_TIMESTAMP | MysqlColumnType::MYSQL_TYPE_TIMESTAMP2 => 93,
+        MysqlColumnType::MYSQL_TYPE_DATETIME | MysqlColumnType::MYSQL_TYPE_DATETIME2 => 93,
+        MysqlColumnType::MYSQL_TYPE_DATE | MysqlColumnType::MYSQL_TYPE_NEWDATE => 91,
+        // the value is a bare YYYY, which jdbc with yearIsDateType=false reports as SMALLINT
+        MysqlColumnType::MYSQL_TYPE_YEAR => 5,
+        MysqlColumnType::MYSQL_TYPE_TIME | MysqlColumnType::MYSQL_TYPE_TIME2 => 92,
+        MysqlColumnType::MYSQL_TYPE_BIT => -7,
+        MysqlColumnType::MYSQL_TYPE_VARCHAR | MysqlColumnType::MYSQL_TYPE_VAR_STRING if binary => -3,
+        MysqlColumnType::MYSQL_TYPE_VARCHAR | MysqlColumnType::MYSQL_TYPE_VAR_STRING => 12,
+        MysqlColumnType::MYSQL_TYPE_STRING if binary => -2,
+        MysqlColumnType::MYSQL_TYPE_STRING | MysqlColumnType::MYSQL_TYPE_ENUM | MysqlColumnType::MYSQL_TYPE_SET => 1,
+        MysqlColumnType::MYSQL_TYPE_TINY_BLOB | MysqlColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | MysqlColumnType::MYSQL_TYPE_LONG_BLOB | MysqlColumnType::MYSQL_TYPE_BLOB if binary => -4,
+        MysqlColumnType::MYSQL_TYPE_TINY_BLOB | MysqlColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | MysqlColumnType::MYSQL_TYPE_LONG_BLOB | MysqlColumnType::MYSQL_TYPE_BLOB => -1,
+        MysqlColumnType::MYSQL_TYPE_JSON => -1,
+        MysqlColumnType::MYSQL_TYPE_GEOMETRY => -2,
+    }
+}
+// the type name jdbc gives along with the columnMetadata.type above, not the protocol name
+fn mysql_type_name(column: &MysqlColumn) -> String {
+    let binary = column.character_set() == BINARY_CHARSET;
+    let flags = column.flags();
+    let type_name = match column.column_type() {
+        MysqlColumnType::MYSQL_TYPE_DECIMAL | MysqlColumnType::MYSQL_TYPE_NEWDECIMAL => "DECIMAL",
+        MysqlColumnType::MYSQL_TYPE_TINY if column.column_length() == 1 => return "BIT".to_owned(),
+        MysqlColumnType::MYSQL_TYPE_TINY => "TINYINT",
+        MysqlColumnType::MYSQL_TYPE_SHORT => "SMALLINT",
+        MysqlColumnType::MYSQL_TYPE_INT24 => "MEDIUMINT",
+        MysqlColumnType::MYSQL_TYPE_LONG => "INT",
+        MysqlColumnType::MYSQL_TYPE_LONGLONG => "BIGINT",
+        MysqlColumnType::MYSQL_TYPE_FLOAT => "FLOAT",
+        MysqlColumnType::MYSQL_TYPE_DOUBLE => "DOUBLE",
+        MysqlColumnType::MYSQL_TYPE_NULL => "NULL",
+        MysqlColumnType::MYSQL_TYPE_TIMESTAMP | MysqlColumnType::MYSQL_TYPE_TIMESTAMP2 => "TIMESTAMP",
+        MysqlColumnType::MYSQL_TYPE_DATETIME | MysqlColumnType::MYSQL_TYPE_DATETIME2 => "DATETIME",
+        MysqlColumnType::MYSQL_TYPE_DATE | MysqlColumnType::MYSQL_TYPE_NEWDATE => "DATE",
+        MysqlColumnType::MYSQL_TYPE_YEAR => "YEAR",
+        MysqlColumnType::MYSQL_TYPE_TIME | MysqlColumnType::MYSQL_TYPE_TIME2 => "TIME",
+        MysqlColumnType::MYSQL_TYPE_BIT => "BIT",
+        MysqlColumnType::MYSQL_TYPE_VARCHAR | MysqlColumnType::MYSQL_TYPE_VAR_STRING if binary => "VARBINARY",
+        MysqlColumnType::MYSQL_TYPE_VARCHAR | MysqlColumnType::MYSQL_TYPE_VAR_STRING => "VARCHAR",
+        // ENUM and SET columns are sent as STRING with a flag
+        MysqlColumnType::MYSQL_TYPE_STRING if flags.contains(MysqlColumnFlags::ENUM_FLAG) => "ENUM",
+        MysqlColumnType::MYSQL_TYPE_STRING if flags.contains(MysqlColumnFlags::SET_FLAG) => "SET",
+        MysqlColumnType::MYSQL_TYPE_STRING if binary => "BINARY",
+        MysqlColumnType::MYSQL_TYPE_STRING => "CHAR",
+        MysqlColumnType::MYSQL_TYPE_ENUM => "ENUM",
+        MysqlColumnType::MYSQL_TYPE_SET => "SET",
+        // every TEXT and BLOB column is sent as BLOB, only its length in characters tells the size
+        MysqlColumnType::MYSQL_TYPE_TINY_BLOB | MysqlColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | MysqlColumnType::MYSQL_TYPE_LONG_BLOB | MysqlColumnType::MYSQL_TYPE_BLOB => {
+            let size = match column.column_length() as u64 / charset_max_len(column.character_set()) {
+                0..=255 => "TINY",
+                256..=65535 => "",
+                65536..=16777215 => "MEDIUM",
+                _ => "LONG",
+            };
+            return format!("{}{}", size, if binary { "BLOB" } else { "TEXT" });
+        },
+        MysqlColumnType::MYSQL_TYPE_JSON => "JSON",
+        MysqlColumnType::MYSQL_TYPE_GEOMETRY => "GEOMETRY",
+    };
+    if is_numeric_column_type(column.column_type()) && flags.contains(MysqlColumnFlags::UNSIGNED_FLAG) {
+        format!("{} UNSIGNED", type_name)
+    } else {
+        type_name.to_owned()
+    }
+}
+fn is_numeric_column_type(column_type: MysqlColumnType) -> bool {
+    matches!(column_type, MysqlColumnType::MYSQL_TYPE_DECIMAL | MysqlColumnType::MYSQL_TYPE_NEWDECIMAL
+        | MysqlColumnType::MYSQL_TYPE_TINY | MysqlColumnType::MYSQL_TYPE_SHORT
+        | MysqlColumnType::MYSQL_TYPE_LONG | MysqlColumnType::MYSQL_TYPE_INT24
+        | MysqlColumnType::MYSQL_TYPE_LONGLONG | MysqlColumnType::MYSQL_TYPE_FLOAT
+        | MysqlColumnType::MYSQL_TYPE_DOUBLE)
+}
+fn is_string_column_type(column_type: MysqlColumnType) -> bool {
+    matches!(column_type, MysqlColumnType::MYSQL_TYPE_VARCHAR | MysqlColumnType::MYSQL_TYPE_VAR_STRING
+        | MysqlColumnType::MYSQL_TYPE_STRING | MysqlColumnType::MYSQL_TYPE_ENUM
+        | MysqlColumnType::MYSQL_TYPE_SET | MysqlColumnType::MYSQL_TYPE_JSON
+        | MysqlColumnType::MYSQL_TYPE_TINY_BLOB | MysqlColumnType::MYSQL_TYPE_MEDIUM_BLOB
+        | MysqlColumnType::MYSQL_TYPE_LONG_BLOB | MysqlColumnType::MYSQL_TYPE_BLOB)
+}
+// maximum bytes per character of a charset id, column_length of a string column is in bytes
+fn charset_max_len(character_set: u16) -> u64 {
+    match character_set {
+        33 | 83 | 192..=215 | 223 => 3, // utf8
+        45 | 46 | 224..=247 | 255..=323 => 4, // utf8mb4
+        35 | 90 | 128..=151 | 159 => 2, // ucs2
+        54 | 55 | 56 | 62 | 101..=124 | 160..=183 => 4, // utf16, utf16le, utf32
+        1 | 84 | 13 | 88 | 19 | 85 | 24 | 86 | 28 | 87 | 95 | 96 => 2, // big5, sjis, euckr, gb2312, gbk, cp932
+        12 | 91 | 97 | 98 => 3, // ujis, eucjpms
+        248..=250 => 4, // gb18030
+        _ => 1,
+    }
+}
+fn mysql_precision(column: &MysqlColumn) -> u64 {
+    let column_length = column.column_length() as u64;
+    match column.column_type() {
+        MysqlColumnType::MYSQL_TYPE_DECIMAL | MysqlColumnType::MYSQL_TYPE_NEWDECIMAL => {
+            // the display length counts the sign and the decimal point
+            let sign = if column.flags().contains(MysqlColumnFlags::UNSIGNED_FLAG) { 0 } else { 1 };
+            let point = if column.decimals() > 0 { 1 } else { 0 };
+            column_length.saturating_sub(sign + point)
+        },
+        column_type if is_string_column_type(column_type) => column_length / charset_max_len(column.character_set()),
+        _ => column_length,
+    }
+}
+fn mysql_scale(column: &MysqlColumn) -> u64 {
+    match column.column_type() {
+        // 31 is mysql for not fixed, a FLOAT or DOUBLE without (M,D)
+        MysqlColumnType::MYSQL_TYPE_DECIMAL | MysqlColumnType::MYSQL_TYPE_NEWDECIMAL
+        | MysqlColumnType::MYSQL_TYPE_FLOAT | MysqlColumnType::MYSQL_TYPE_DOUBLE
+        | MysqlColumnType::MYSQL_TYPE_TIMESTAMP | MysqlColumnType::MYSQL_TYPE_TIMESTAMP2
+        | MysqlColumnType::MYSQL_TYPE_DATETIME | MysqlColumnType::MYSQL_TYPE_DATETIME2
+        | MysqlColumnType::MYSQL_TYPE_TIME | MysqlColumnType::MYSQL_TYPE_TIME2 if column.decimals() < 31 => column.decimals() as u64,
+        _ => 0,
+    }
+}
+// the encoding of a charset id, text of an unknown charset has to be utf-8
+fn charset_encoding(character_set: u16) -> &'static Encoding {
+    match character_set {
+        5 | 8 | 11 | 15 | 31 | 47 | 48 | 49 | 65 | 94 => encoding_rs::WINDOWS_1252, // latin1 is cp1252 in mysql, ascii
+        9 | 21 | 27 | 77 => encoding_rs::ISO_8859_2, // latin2
+        16 | 71 => encoding_rs::ISO_8859_8, // hebrew
+        20 | 41 | 42 | 79 => encoding_rs::ISO_8859_13, // latin7
+        25 | 70 => encoding_rs::ISO_8859_7, // greek
+        26 | 34 | 44 | 66 | 99 => encoding_rs::WINDOWS_1250, // cp1250
+        14 | 23 | 50 | 51 | 52 => encoding_rs::WINDOWS_1251, // cp1251
+        57 | 67 => encoding_rs::WINDOWS_1256, // cp1256
+        29 | 58 | 59 => encoding_rs::WINDOWS_1257, // cp1257
+        30 | 78 => encoding_rs::WINDOWS_1254, // latin5
+        7 | 74 => encoding_rs::KOI8_R,
+        22 | 75 => encoding_rs::KOI8_U,
+        1 | 84 => encoding_rs::BIG5,
+        13 | 88 | 95 | 96 => encoding_rs::SHIFT_JIS, // sjis, cp932
+        12 | 91 | 97 | 98 => encoding_rs::EUC_JP, // ujis, eucjpms
+        19 | 85 => encoding_rs::EUC_KR,
+        24 | 86 | 28 | 87 => encoding_rs::GBK, // gb2312, gbk
+        248..=250 => encoding_rs::GB18030,
+        54 | 55 | 101..=124 => encoding_rs::UTF_16BE, // utf16
+        56 | 62 => encoding_rs::UTF_16LE,
+        _ => encoding_rs::UTF_8,
+    }
+}
+fn decode_mysql_text(label: &str, character_set: u16, bytes: &[u8]) -> Result<String, Error> {
+    let encoding = charset_encoding(character_set);
+    match encoding.decode_without_bom_handling_and_without_replacement(bytes) {
+        Some(text) => Ok(text.into_owned()),
+        None => Err(Error {
+            msg: format!("Column {} is not valid {} text", label, encoding.name()),
+            error_type: ErrorType::UnsupportedResultException,
+        }),
+    }
+}
+fn map_mysql_column_metadata(column: &MysqlColumn, mapped_mysql_column_type: &MappedMysqlColumnType) -> ColumnMetadata {
+    let column_type = column.column_type();
+    let column_flags = column.flags();
+    // strings compare case sensitively with a binary charset or a _bin collation, which mysql flags as BINARY
+    let is_case_sensitive = is_string_column_type(column_type)
+        && (column.character_set() == BINARY_CHARSET || column_flags.contains(MysqlColumnFlags::BINARY_FLAG));
+    ColumnMetadata {
+        array_base_column_type: Some(0),
+        is_auto_increment: Some(column_flags.contains(MysqlColumnFlags::AUTO_INCREMENT_FLAG)),
+        is_case_sensitive: Some(is_case_sensitive),
+        is_currency: Some(false),
+        is_signed: Some(is_numeric_column_type(column_type) && !column_flags.contains(MysqlColumnFlags::UNSIGNED_FLAG)),
+        label: Some(column.name_str().to_string()),
+        name: Some(column.org_name_str().to_string()),
+        // java.sql.ResultSetMetaData columnNoNulls is 0, columnNullable is 1
+        nullable: Some(if column_flags.contains(MysqlColumnFlags::NOT_NULL_FLAG) { 0 } else { 1 }),
+        precision: Some(mysql_precision(column)),
+        scale: Some(mysql_scale(column)),
+        schema_name: Some(column.schema_str().to_string()),
+        table_name: Some(column.table_str().to_string()),
+        type_: Some(mysql_jdbc_type(column)),
+        type_name: Some(mapped_mysql_column_type.type_name.clone()),
+    }
+}
+fn is_decimal_column_type(column_type: MysqlColumnType) -> bool {
+    matches!(column_type, MysqlColumnType::MYSQL_TYPE_DECIMAL | MysqlColumnType::MYSQL_TYPE_NEWDECIMAL)
+}
+// BIGINT UNSIGNED above i64::MAX keeps its digits as stringValue instead of wrapping around
+fn unsigned_field(value: u64) -> Field {
+    if value > i64::MAX as u64 {
+        Field::StringValue(value.to_string())
+    } else {
+        Field::LongValue(value as i64)
+    }
+}
+fn is_temporal_column_type(column_type: MysqlColumnType) -> bool {
+    matches!(column_type, MysqlColumnType::MYSQL_TYPE_TIMESTAMP | MysqlColumnType::MYSQL_TYPE_TIMESTAMP2
+        | MysqlColumnType::MYSQL_TYPE_DATETIME | MysqlColumnType::MYSQL_TYPE_DATETIME2
+        | MysqlColumnType::MYSQL_TYPE_DATE | MysqlColumnType::MYSQL_TYPE_NEWDATE
+        | MysqlColumnType::MYSQL_TYPE_TIME | MysqlColumnType::MYSQL_TYPE_TIME2)
+}
+// the data api renders fractional seconds like java.sql.Timestamp, without trailing zeros and left out when zero
+fn format_fraction(micros: u32) -> String {
+    if micros == 0 {
+        String::new()
+    } else {
+        format!(".{:06}", micros).trim_end_matches('0').to_string()
+    }
+}
+// binary protocol, zero dates arrive as all zero parts and keep them
+#[allow(clippy::too_many_arguments)]
+fn format_mysql_date(column_type: MysqlColumnType, year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8, micros: u32) -> String {
+    match column_type {
+        MysqlColumnType::MYSQL_TYPE_DATE | MysqlColumnType::MYSQL_TYPE_NEWDATE => format!("{:04}-{:02}-{:02}", year, month, day),
+        _ => format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}{}", year, month, day, hour, minute, second, format_fraction(micros)),
+    }
+}
+// TIME is a duration up to 838:59:59 either way, the days go into the hours
+fn format_mysql_time(is_negative: bool, days: u32, hours: u8, minutes: u8, seconds: u8, micros: u32) -> String {
+    let sign = if is_negative { "-" } else { "" };
+    format!("{}{:02}:{:02}:{:02}{}", sign, days * 24 + hours as u32, minutes, seconds, format_fraction(micros))
+}
+// text protocol, mysql already renders the value, only the fraction padded to the column precision differs
+fn format_mysql_temporal_text(text: &str) -> String {
+    match text.find('.') {
+        Some(point) => {
+            let micros = format!("{:0<6}", &text[point + 1..]).parse::<u32>().unwrap_or(0);
+            format!("{}{}", &text[..point], format_fraction(micros))
+        },
+        None => text.to_string(),
+    }
+}
+// the data api takes up to 64 characters, the mysql identifier limit too
+const MAX_DATABASE_NAME_LENGTH: usize = 64;
+
+// an empty database keeps the current one, an unknown one fails with DatabaseNotFoundException (ER_BAD_DB_ERROR)
+fn select_database_and_schema(conn: &mut mysql::Conn, database: Option<String>, schema: Option<String>) -> Result<(), Error> {
+    // a mysql schema is a database, aurora mysql does not take one
+    if schema.is_some_and(|schema| !schema.is_empty()) {
+        return Err(Error {
+            msg: "The schema parameter isn't supported for Aurora MySQL, use database instead".to_owned(),
+            error_type: ErrorType::BadRequestException,
+        });
+    }
+    match database {
+        Some(ref db) if db.chars().count() > MAX_DATABASE_NAME_LENGTH => Err(Error {
+            msg: format!("1 validation error detected: Value '{}' at 'database' failed to satisfy constraint: Member must have length less than or equal to {}", db, MAX_DATABASE_NAME_LENGTH),
+            error_type: ErrorType::BadRequestException,
+        }),
+        Some(ref db) if !db.is_empty() => {
+            conn.query(format!("USE {}", quote_identifier(db)))?;
+            Ok(())
+        },
+        _ => Ok(()),
+    }
+}
+// backquoted with backquotes doubled, any name mysql accepts can be used and none can end the statement
+fn quote_identifier(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
+}
+fn put_param_to_hashmap(hashmap: &mut HashMap::<String, MysqlValue, BuildHasherDefault<XxHash>>, paramnamemap: &HashMap<String, String>, _sqlstr: &str, parameters: Vec<SqlParameter>, _originalsql: &str)-> Result<(), Error> {
+    for parameter in parameters {
+        match paramnamemap.get(&parameter.name) {
+            Some(snake_name) => {
+                match parameter.value {
+                    Field::ArrayValue(_) => return Err(unsupported_parameter_error("Array")),
+                    Field::StructValue(_) => return Err(unsupported_parameter_error("Struct")),
+                    Field::BlobValue(value) => {
+                        hashmap.insert(snake_name.to_string(), MysqlValue::Bytes(decode_blob(&parameter.name, &value)?));
+                    },
+                    Field::BooleanValue(value) => {
+                        let boolint: u64 = if value {
+                            1
+                        } else {
+                            0
+                        };
+                        hashmap.insert(snake_name.to_string(), MysqlValue::UInt(boolint));
+                    },
+                    Field::DoubleValue(value) => {
+                        hashmap.insert(snake_name.to_string(), MysqlValue::Float(value));
+                    },
+                    Field::IsNull(_) => {
+                        hashmap.insert(snake_name.to_string(), MysqlValue::NULL);
+                    },
+                    Field::LongValue(value) => {
+                        hashmap.insert(snake_name.to_string(), MysqlValue::Int(value));
+                    },
+                    Field::StringValue(value) => {
+                        let mysql_value = match parameter.type_hint {
+                            Some(type_hint) => to_mysql_value(parse_type_hint(&parameter.name, &value, type_hint)?),
+                            None => MysqlValue::Bytes(value.into_bytes()),
+                        };
+                        hashmap.insert(snake_name.to_string(), mysql_value);
+                    },
+                }
+            },
+            None => {
+                // println!("{:?} {:?}", parameter.name, paramnamemap);
+                // return Err(Error{
+                //     msg: format!("invalid sql: {}, debug: {}", originalsql, sqlstr),
+                //     error_type: ErrorType::BadRequestException
+                // })
+            }
+        }
+    }
+    Ok(())
+}
+// DATE, TIME and TIMESTAMP are bound as temporal values, the rest as text mysql converts itself
+fn to_mysql_value(hinted_value: HintedValue) -> MysqlValue {
+    match hinted_value {
+        HintedValue::Date(date) => MysqlValue::Date(date.year() as u16, date.month() as u8, date.day() as u8, 0, 0, 0, 0),
+        HintedValue::Time(time) => MysqlValue::Time(false, 0, time.hour() as u8, time.minute() as u8, time.second() as u8, time.nanosecond() / 1000),
+        HintedValue::Timestamp(timestamp) => MysqlValue::Date(timestamp.year() as u16, timestamp.month() as u8, timestamp.day() as u8, timestamp.hour() as u8, timestamp.minute() as u8, timestamp.second() as u8, timestamp.nanosecond() / 1000),
+        HintedValue::Decimal(decimal) => MysqlValue::Bytes(decimal.into_bytes()),
+        HintedValue::Json(json) => MysqlValue::Bytes(json.to_string().into_bytes()),
+        HintedValue::Uuid(uuid) => MysqlValue::Bytes(uuid.into_bytes()),
+    }
+}
+fn format_sql_to_snake(sqlstr: String) -> (String, HashMap<String, String>){
+    // for rust-mysql v17 issue with camel case param name
+    let (targetsql, paramidxmap) = format_sql_params(sqlstr, |idx| format!(":q{}", idx));
+    let paramnamemap = paramidxmap.into_iter()
+        .map(|(param, idx)| (param, format!("q{}", idx)))
+        .collect();
+    (targetsql, paramnamemap)
+}
+fn execute(conn: &mut mysql::Conn, sql: &str, parameters: Vec<SqlParameter>, options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+    let (sqlstr, paramnamemap) = format_sql_to_snake(sql.to_owned());
+    let params = if !parameters.is_empty() {
+        let mut hashmap = HashMap::<String, MysqlValue, BuildHasherDefault<XxHash>>::default();
+        put_param_to_hashmap(&mut hashmap, &paramnamemap, &sqlstr, parameters, sql)?;
+        mysql::Params::Named(hashmap)
+    } else {
+        mysql::Params::Empty
+    };
+    let mut result = if params == mysql::Params::Empty {
+        conn.query(sqlstr)?
+    } else {
+        conn.prep_exec(sqlstr, params)?
+    };
+    format_prep_exec_result(&mut result, options)
+}
+fn format_prep_exec_result(query_result: &mut mysql::QueryResult, options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+    let mut records: Vec<Vec<Field>> = Vec::new();
+    let mut response_size = ResponseSize::new(options);
+    let mut column_metadata: Vec<ColumnMetadata> = Vec::new();
+    let need_column_metadata = options.include_result_metadata;
+    let mut column_types: Vec<MappedMysqlColumnType> = Vec::new();
+    let mut labels: Vec<String> = Vec::new();
+    let generated_fields: Option<Vec<Field>> = if query_result.last_insert_id() == 0 {
+        None
+    } else {
+        Some(vec![Field::LongValue(query_result.last_insert_id() as i64)])
+    };
+    for x in query_result.columns_ref() {
+        let mapped_mysql_column_type = map_mysql_column_type(x, options);
+        column_types.push(mapped_mysql_column_type.clone());
+        labels.push(x.name_str().to_string());
+        if need_column_metadata {
+            column_metadata.push(map_mysql_column_metadata(x, &mapped_mysql_column_type));
+        };
+    }
+    while query_result.more_results_exists() {
+        for x in query_result.by_ref() {
+            let mut record: Vec<Field> = Vec::new();
+            let row = x?;
+            for i in 0..row.len() {
+                let mapped_mysql_column_type = &column_types[i];
+                let value = match row.as_ref(i) {
+                    Some(value) => value,
+                    None => return Err(Error {
+                        msg: format!("Column {} has no value", labels[i]),
+                        error_type: ErrorType::UnsupportedResultException,
+                    }),
+                };
+                let field_option: Result<Field, String> = match value {
+                    MysqlValue::NULL => {
+                        Ok(Field::IsNull(true))
+                    },
+                    MysqlValue::Int(value) if mapped_mysql_column_type.column_type == MysqlColumnType::MYSQL_TYPE_YEAR => {
+                        Ok(Field::StringValue(format!("{:04}", value)))
+                    },
+                    MysqlValue::Int(value) if mapped_mysql_column_type.column_field == ColumnField::StringValue => {
+                        Ok(Field::StringValue(value.to_string()))
+                    },
+                    MysqlValue::Int(value) if mapped_mysql_column_type.column_field == ColumnField::BooleanValue => {
+                        Ok(Field::BooleanValue(*value != 0))
+                    },
+                    MysqlValue::Int(value) => {
+                        Ok(Field::LongValue(*value))
+                    },
+                    MysqlValue::UInt(value) if mapped_mysql_column_type.column_field == ColumnField::StringValue => {
+                        Ok(Field::StringValue(value.to_string()))
+                    },
+                    MysqlValue::UInt(value) if mapped_mysql_column_type.column_field == ColumnField::BooleanValue => {
+                        Ok(Field::BooleanValue(*value != 0))
+                    },
+                    MysqlValue::UInt(value) => {
+                        Ok(unsigned_field(*value))
+                    },
+                    MysqlValue::Float(value) => {
+                        Ok(Field::DoubleValue(*value))
+                    },
+                    MysqlValue::Date(year, month, day, hour, minute, second, micros) => {
+                        Ok(Field::StringValue(format_mysql_date(mapped_mysql_column_type.column_type, *year, *month, *day, *hour, *minute, *second, *micros)))
+                    },
+                    MysqlValue::Time(is_negative, days, hours, minutes, seconds, micros) => {
+                        Ok(Field::StringValue(format_mysql_time(*is_negative, *days, *hours, *minutes, *seconds, *micros)))
+                    },
+                    MysqlValue::Bytes(byte) if is_decimal_column_type(mapped_mysql_column_type.column_type) => {
+                        Ok(decimal_field(String::from_utf8_lossy(byte).to_string(), options.decimal_return_type))
+                    },
+                    MysqlValue::Bytes(byte) if is_temporal_column_type(mapped_mysql_column_type.column_type) => {
+                        Ok(Field::StringValue(format_mysql_temporal_text(&String::from_utf8_lossy(byte))))
+                    },
+                    MysqlValue::Bytes(byte) => {
+                        match mapped_mysql_column_type.column_field {
+                            ColumnField::StringValue => {
+                                Ok(Field::StringValue(decode_mysql_text(&labels[i], mapped_mysql_column_type.character_set, byte)?))
+                            },
+                            ColumnField::BlobValue => {
+                                Ok(Field::BlobValue(base64::encode(byte)))
+                            },
+                            ColumnField::BooleanValue if mapped_mysql_column_type.column_type == MysqlColumnType::MYSQL_TYPE_BIT => {
+                                // BIT is sent as big endian bytes in both protocols
+                                Ok(Field::BooleanValue(byte.iter().any(|b| *b != 0)))
+                            },
+                            ColumnField::BooleanValue => {
+                                let stringvalue = String::from_utf8_lossy(byte).to_string();
+                                match stringvalue.parse::<i64>() {
+                                    Ok(value) => Ok(Field::BooleanValue(value != 0)),
+                                    Err(_) => Err(format!("unexpected convertion to boolean error from {:?}", stringvalue)),
+                                }
+                            },
+                            ColumnField::IsNull => {
+                                Ok(Field::IsNull(true))
+                            },
+                            ColumnField::LongValue => {
+                                let stringvalue = String::from_utf8_lossy(byte).to_string();
+                                match (stringvalue.parse::<i64>(), stringvalue.parse::<u64>()) {
+                                    (Ok(value), _) => Ok(Field::LongValue(value)),
+                                    (_, Ok(value)) => Ok(unsigned_field(value)),
+                                    _ => Err(format!("unexpected convertion to long error from {:?}", stringvalue)),
+                                }
+                            },
+                            ColumnField::DoubleValue => {
+                                let stringvalue = String::from_utf8_lossy(byte).to_string();
+                                match stringvalue.parse::<f64>() {
+                                    Ok(value) => Ok(Field::DoubleValue(value)),
+                                    Err(_) => Err(format!("unexpected convertion to double error from {:?}", stringvalue)),
+                                }
+                            },
+                        }
+                    },
+                };
+                match field_option {
+                    Ok(field) => record.push(field),
+                    Err(msg) => return Err(Error {
+                        msg: msg,
+                        error_type: ErrorType::UnsupportedResultException,
+                    }),
+                }
+            }
+            response_size.add(&record)?;
+            records.push(record);
+        }
+    }
+    let (records, formatted_records) = format_records(records, &labels, options);
+    Ok(ExecuteStatementResponse {
+        number_of_records_updated: query_result.affected_rows(),
+        generated_fields: generated_fields,
+        records: records,
+        column_metadata: Some(column_metadata),
+        formatted_records: formatted_records,
+    })
+}
+fn format_batch_exec_result(query_result: &mut mysql::QueryResult) -> Result<Vec<Field>, Error> {
+    let generated_fields: Vec<Field> = vec![Field::LongValue(query_result.last_insert_id() as i64)];
+    Ok(generated_fields)
+}
+fn batch_execute(conn: &mut mysql::Conn, sql: &str, parameter_sets: Vec<Vec<SqlParameter>>) -> Result<Vec<UpdateResult>, Error> {
+    let (sqlstr, paramnamemap) = format_sql_to_snake(sql.to_owned());
+    let mut update_results: Vec<UpdateResult> = Vec::with_capacity(parameter_sets.len());
+    for parameters in parameter_sets {
+        let mut hashmap = HashMap::<String, MysqlValue, BuildHasherDefault<XxHash>>::default();
+        put_param_to_hashmap(&mut hashmap, &paramnamemap, &sqlstr, parameters, sql)?;
+        let mut result = conn.prep_exec(sqlstr.clone(), MysqlParams::Named(hashmap))?;
+        let generated_fields = format_batch_exec_result(&mut result)?;
+        update_results.push(UpdateResult {
+            generated_fields: generated_fields,
+        });
+    }
+    Ok(update_results)
+}
@@ -0,0 +1,593 @@
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::{BufMut, BytesMut};
+use futures::channel::oneshot;
+use futures::lock::Mutex;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use tokio_postgres::{CancelToken, Client, Column, Config, NoTls, Row};
+use tokio_postgres::types::{to_sql_checked, FromSql, IsNull, Kind, ToSql, Type};
+
+use super::{decimal_field, decode_blob, format_records, format_sql_params, long_field, parse_type_hint, statement_canceled_error, unsupported_parameter_error, with_statement_timeout, Backend, Connection, ExecuteOptions, HintedValue, ResponseSize};
+use crate::config::{ClusterConfig, SecretConfig};
+use crate::model::{ArrayValue, ColumnMetadata, Error, ErrorType, ExecuteStatementResponse, Field, SqlParameter, UpdateResult};
+
+// NULL has no type of its own, so it is accepted for whatever type postgres infers for the placeholder
+#[derive(Debug)]
+struct PgNull;
+
+impl ToSql for PgNull {
+    fn to_sql(&self, _ty: &Type, _out: &mut BytesMut) -> Result<IsNull, Box<dyn StdError + Sync + Send>> {
+        Ok(IsNull::Yes)
+    }
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+    to_sql_checked!();
+}
+
+// NUMERIC is rendered as stringValue, same as the Data API does for Aurora PostgreSQL
+#[derive(Debug)]
+struct PgNumeric(String);
+
+impl<'a> FromSql<'a> for PgNumeric {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn StdError + Sync + Send>> {
+        // binary format: ndigits, weight, sign, dscale, then ndigits base-10000 digits
+        if raw.len() < 8 {
+            return Err("invalid numeric value".into());
+        }
+        let read = |i: usize| i16::from_be_bytes([raw[i], raw[i + 1]]);
+        let (ndigits, weight, sign, dscale) = (read(0), read(2) as i32, read(4) as u16, read(6));
+        if ndigits < 0 || dscale < 0 || raw.len() < 8 + ndigits as usize * 2 {
+            return Err("invalid numeric value".into());
+        }
+        let (ndigits, dscale) = (ndigits as usize, dscale as usize);
+        // the special values have no digits, infinity exists since postgres 14
+        match sign {
+            0x0000 | 0x4000 => {},
+            0xC000 => return Ok(PgNumeric("NaN".to_owned())),
+            0xD000 => return Ok(PgNumeric("Infinity".to_owned())),
+            0xF000 => return Ok(PgNumeric("-Infinity".to_owned())),
+            _ => return Err("invalid numeric sign".into()),
+        }
+        let digit = |pos: i32| if pos >= 0 && (pos as usize) < ndigits {
+            read(8 + pos as usize * 2)
+        } else {
+            0
+        };
+        let mut numeric = String::new();
+        if sign == 0x4000 {
+            numeric.push('-');
+        }
+        if weight < 0 {
+            numeric.push('0');
+        } else {
+            numeric.push_str(&digit(0).to_string());
+            for pos in 1..=weight {
+                numeric.push_str(&format!("{:04}", digit(pos)));
+            }
+        }
+        if dscale > 0 {
+            let mut fraction = String::new();
+            let mut pos = weight + 1;
+            while fraction.len() < dscale {
+                fraction.push_str(&format!("{:04}", digit(pos)));
+                pos += 1;
+            }
+            fraction.truncate(dscale);
+            numeric.push('.');
+            numeric.push_str(&fraction);
+        }
+        Ok(PgNumeric(numeric))
+    }
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+}
+
+// typeHint DECIMAL, the string is already checked to be [-]digits[.digits]
+impl ToSql for PgNumeric {
+    fn to_sql(&self, _ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn StdError + Sync + Send>> {
+        let (negative, digits) = match self.0.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, self.0.trim_start_matches('+')),
+        };
+        let mut parts = digits.splitn(2, '.');
+        let integer = parts.next().unwrap_or("").trim_start_matches('0');
+        let fraction = parts.next().unwrap_or("");
+        // base-10000 digits, the integer part grouped from the decimal point to the left and the fraction to the right
+        let integer = format!("{}{}", "0".repeat((4 - integer.len() % 4) % 4), integer);
+        let padded_fraction = format!("{}{}", fraction, "0".repeat((4 - fraction.len() % 4) % 4));
+        let mut groups: Vec<i16> = integer.as_bytes().chunks(4).chain(padded_fraction.as_bytes().chunks(4))
+            .map(|chunk| chunk.iter().fold(0i16, |group, digit| group * 10 + (digit - b'0') as i16))
+            .collect();
+        let mut weight = (integer.len() / 4) as i16 - 1;
+        while groups.first() == Some(&0) {
+            groups.remove(0);
+            weight -= 1;
+        }
+        while groups.last() == Some(&0) {
+            groups.pop();
+        }
+        if groups.is_empty() {
+            weight = 0;
+        }
+        out.put_i16(groups.len() as i16);
+        out.put_i16(weight);
+        out.put_u16(if negative && !groups.is_empty() { 0x4000 } else { 0 });
+        out.put_u16(fraction.len() as u16);
+        for group in groups {
+            out.put_i16(group);
+        }
+        Ok(IsNull::No)
+    }
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+    to_sql_checked!();
+}
+
+// an array of any number of dimensions, tokio-postgres only reads one
+struct PgArray<T> {
+    dimensions: Vec<usize>,
+    elements: Vec<Option<T>>,
+}
+
+fn take_pg_bytes<'a>(raw: &mut &'a [u8], len: usize) -> Result<&'a [u8], Box<dyn StdError + Sync + Send>> {
+    if raw.len() < len {
+        return Err("invalid array value".into());
+    }
+    let (bytes, rest) = raw.split_at(len);
+    *raw = rest;
+    Ok(bytes)
+}
+
+fn take_pg_i32(raw: &mut &[u8]) -> Result<i32, Box<dyn StdError + Sync + Send>> {
+    let bytes = take_pg_bytes(raw, 4)?;
+    Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+impl<'a, T: FromSql<'a>> FromSql<'a> for PgArray<T> {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn StdError + Sync + Send>> {
+        let element_type = match ty.kind() {
+            Kind::Array(element_type) => element_type,
+            _ => return Err("not an array type".into()),
+        };
+        // binary format: ndim, has nulls, element oid, size and lower bound of each dimension,
+        // then every element as its length and bytes, length -1 for NULL
+        let mut raw = raw;
+        let ndim = take_pg_i32(&mut raw)?;
+        take_pg_bytes(&mut raw, 8)?;
+        let mut dimensions: Vec<usize> = Vec::new();
+        for _ in 0..ndim {
+            let size = take_pg_i32(&mut raw)?;
+            take_pg_bytes(&mut raw, 4)?;
+            if size < 0 {
+                return Err("invalid array value".into());
+            }
+            dimensions.push(size as usize);
+        }
+        let count = if dimensions.is_empty() { 0 } else { dimensions.iter().product() };
+        let mut elements: Vec<Option<T>> = Vec::new();
+        for _ in 0..count {
+            let len = take_pg_i32(&mut raw)?;
+            elements.push(if len < 0 {
+                None
+            } else {
+                Some(T::from_sql(element_type, take_pg_bytes(&mut raw, len as usize)?)?)
+            });
+        }
+        Ok(PgArray {
+            dimensions: dimensions,
+            elements: elements,
+        })
+    }
+    fn accepts(ty: &Type) -> bool {
+        match ty.kind() {
+            Kind::Array(element_type) => T::accepts(element_type),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PgUuid(String);
+
+impl<'a> FromSql<'a> for PgUuid {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn StdError + Sync + Send>> {
+        if raw.len() != 16 {
+            return Err("invalid uuid value".into());
+        }
+        let hex: Vec<String> = raw.iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(PgUuid(format!("{}-{}-{}-{}-{}", hex[0..4].concat(), hex[4..6].concat(), hex[6..8].concat(), hex[8..10].concat(), hex[10..16].concat())))
+    }
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::UUID
+    }
+}
+
+// typeHint UUID, the string is already checked to be 32 hex digits in 8-4-4-4-12 groups
+impl ToSql for PgUuid {
+    fn to_sql(&self, _ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn StdError + Sync + Send>> {
+        let hex: Vec<u8> = self.0.bytes().filter(|ch| *ch != b'-').collect();
+        for pair in hex.chunks(2) {
+            out.put_u8(u8::from_str_radix(std::str::from_utf8(pair)?, 16)?);
+        }
+        Ok(IsNull::No)
+    }
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::UUID
+    }
+    to_sql_checked!();
+}
+
+pub struct PostgresBackend {
+    host: String,
+    port: u16,
+    database: String,
+}
+
+impl PostgresBackend {
+    pub fn new(cluster: &ClusterConfig) -> PostgresBackend {
+        PostgresBackend {
+            host: cluster.host(),
+            port: cluster.port(),
+            database: cluster.database(),
+        }
+    }
+    async fn get_pg_conn(&self, secret: &SecretConfig, database: Option<String>) -> Result<Client, Error> {
+        let pg_database: String = match database {
+            Some(db) => db,
+            None => self.database.clone(),
+        };
+        let (client, connection) = Config::new()
+            .host(&self.host)
+            .port(self.port)
+            .user(&secret.user.clone().unwrap_or_default())
+            .password(secret.password.clone().unwrap_or_default())
+            .dbname(&pg_database)
+            .connect(NoTls)
+            .await
+            .map_err(|err| match err.code() {
+                Some(_) => Error::from(err),
+                // no answer from the server at all
+                None => Error {
+                    msg: format!("Database is unavailable: {}", err),
+                    error_type: ErrorType::DatabaseUnavailableException,
+                },
+            })?;
+        actix_rt::spawn(async move {
+            if let Err(e) = connection.await {
+                log::warn!("postgres connection error: {}", e);
+            }
+        });
+        Ok(client)
+    }
+}
+
+#[async_trait(?Send)]
+impl Backend for PostgresBackend {
+    async fn connect(&self, secret: &SecretConfig, database: Option<String>, schema: Option<String>) -> Result<Box<dyn Connection>, Error> {
+        let client = self.get_pg_conn(secret, database).await?;
+        select_schema(&client, schema).await?;
+        Ok(Box::new(PostgresConnection {
+            cancel_token: client.cancel_token(),
+            client: Arc::new(Mutex::new(client)),
+        }))
+    }
+}
+
+pub struct PostgresConnection {
+    // shared with the statement that is still running after a timeout with continueAfterTimeout
+    client: Arc<Mutex<Client>>,
+    cancel_token: CancelToken,
+}
+
+#[async_trait(?Send)]
+impl Connection for PostgresConnection {
+    async fn begin(&mut self) -> Result<(), Error> {
+        self.client.lock().await.batch_execute("BEGIN").await?;
+        Ok(())
+    }
+    // postgres cannot switch database on an open connection, the database is chosen on connect
+    async fn select_database_and_schema(&mut self, _database: Option<String>, schema: Option<String>) -> Result<(), Error> {
+        select_schema(&*self.client.lock().await, schema).await
+    }
+    // the statement runs in a task of its own that holds the client until it is done, it keeps running with continueAfterTimeout
+    async fn execute(&mut self, sql: &str, parameters: Vec<SqlParameter>, options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+        let client = self.client.clone();
+        let sql = sql.to_owned();
+        let statement_options = options.clone();
+        let (started_tx, started) = oneshot::channel();
+        let (result_tx, result) = oneshot::channel();
+        actix_rt::spawn(async move {
+            let client = client.lock().await;
+            let _ = started_tx.send(());
+            let _ = result_tx.send(execute(&client, &sql, parameters, &statement_options).await);
+        });
+        let statement = async {
+            result.await.unwrap_or_else(|_| Err(statement_canceled_error()))
+        };
+        let cancel = async {
+            self.cancel_token.cancel_query(NoTls).await?;
+            Ok(())
+        };
+        with_statement_timeout(options, started, statement, cancel).await
+    }
+    async fn batch_execute(&mut self, sql: &str, parameter_sets: Vec<Vec<SqlParameter>>) -> Result<Vec<UpdateResult>, Error> {
+        batch_execute(&*self.client.lock().await, sql, parameter_sets).await
+    }
+    async fn commit(&mut self) -> Result<(), Error> {
+        self.client.lock().await.batch_execute("COMMIT").await?;
+        Ok(())
+    }
+    async fn rollback(&mut self) -> Result<(), Error> {
+        self.client.lock().await.batch_execute("ROLLBACK").await?;
+        Ok(())
+    }
+}
+
+async fn select_schema(client: &Client, schema: Option<String>) -> Result<(), Error> {
+    if let Some(schema) = schema {
+        client.batch_execute(&format!("SET search_path TO \"{}\"", schema.replace("\"", "\"\""))).await?;
+    }
+    Ok(())
+}
+
+type PgParam = Box<dyn ToSql + Sync>;
+
+fn to_pg_params(paramidxmap: &HashMap<String, usize>, parameters: Vec<SqlParameter>) -> Result<(Vec<Type>, Vec<PgParam>), Error> {
+    let mut slots: Vec<Option<(Type, PgParam)>> = (0..paramidxmap.len()).map(|_| None).collect();
+    for parameter in parameters {
+        if let Some(idx) = paramidxmap.get(&parameter.name) {
+            let slot: (Type, PgParam) = match parameter.value {
+                Field::ArrayValue(_) => return Err(unsupported_parameter_error("Array")),
+                Field::StructValue(_) => return Err(unsupported_parameter_error("Struct")),
+                Field::BlobValue(value) => (Type::BYTEA, Box::new(decode_blob(&parameter.name, &value)?)),
+                Field::BooleanValue(value) => (Type::BOOL, Box::new(value)),
+                Field::DoubleValue(value) => (Type::FLOAT8, Box::new(value)),
+                Field::IsNull(_) => (Type::UNKNOWN, Box::new(PgNull)),
+                Field::LongValue(value) => (Type::INT8, Box::new(value)),
+                Field::StringValue(value) => match parameter.type_hint {
+                    Some(type_hint) => match parse_type_hint(&parameter.name, &value, type_hint)? {
+                        HintedValue::Date(date) => (Type::DATE, Box::new(date)),
+                        HintedValue::Time(time) => (Type::TIME, Box::new(time)),
+                        HintedValue::Timestamp(timestamp) => (Type::TIMESTAMP, Box::new(timestamp)),
+                        HintedValue::Decimal(decimal) => (Type::NUMERIC, Box::new(PgNumeric(decimal))),
+                        HintedValue::Json(json) => (Type::JSONB, Box::new(json)),
+                        HintedValue::Uuid(uuid) => (Type::UUID, Box::new(PgUuid(uuid))),
+                    },
+                    None => (Type::TEXT, Box::new(value)),
+                },
+            };
+            slots[*idx] = Some(slot);
+        }
+    }
+    let mut types: Vec<Type> = Vec::with_capacity(slots.len());
+    let mut values: Vec<PgParam> = Vec::with_capacity(slots.len());
+    for (param, idx) in paramidxmap {
+        if slots[*idx].is_none() {
+            return Err(Error {
+                msg: format!("No value specified for parameter: {}", param),
+                error_type: ErrorType::BadRequestException,
+            });
+        }
+    }
+    for (ty, value) in slots.into_iter().flatten() {
+        types.push(ty);
+        values.push(value);
+    }
+    Ok((types, values))
+}
+
+async fn execute(client: &Client, sql: &str, parameters: Vec<SqlParameter>, options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+    let (sqlstr, paramidxmap) = format_sql_params(sql.to_owned(), |idx| format!("${}", idx + 1));
+    let (types, values) = to_pg_params(&paramidxmap, parameters)?;
+    let params: Vec<&(dyn ToSql + Sync)> = values.iter().map(|value| value.as_ref()).collect();
+    let statement = client.prepare_typed(&sqlstr, &types).await?;
+    if statement.columns().is_empty() {
+        let number_of_records_updated = client.execute(&statement, &params).await?;
+        let (records, formatted_records) = format_records(Vec::new(), &[], options);
+        return Ok(ExecuteStatementResponse {
+            number_of_records_updated: number_of_records_updated,
+            generated_fields: None,
+            records: records,
+            column_metadata: Some(Vec::new()),
+            formatted_records: formatted_records,
+        });
+    }
+    let rows = client.query(&statement, &params).await?;
+    format_pg_result(statement.columns(), &rows, options)
+}
+
+async fn batch_execute(client: &Client, sql: &str, parameter_sets: Vec<Vec<SqlParameter>>) -> Result<Vec<UpdateResult>, Error> {
+    let (sqlstr, paramidxmap) = format_sql_params(sql.to_owned(), |idx| format!("${}", idx + 1));
+    let mut update_results: Vec<UpdateResult> = Vec::with_capacity(parameter_sets.len());
+    for parameters in parameter_sets {
+        // a NULL in one set and a value in another give different parameter types, so prepare per set
+        let (types, values) = to_pg_params(&paramidxmap, parameters)?;
+        let params: Vec<&(dyn ToSql + Sync)> = values.iter().map(|value| value.as_ref()).collect();
+        let statement = client.prepare_typed(&sqlstr, &types).await?;
+        client.execute(&statement, &params).await?;
+        update_results.push(UpdateResult {
+            generated_fields: Vec::new(),
+        });
+    }
+    Ok(update_results)
+}
+
+fn format_pg_value(row: &Row, idx: usize, column_type: &Type, options: &ExecuteOptions) -> Result<Field, Error> {
+    let field = if *column_type == Type::BOOL {
+        row.try_get::<_, Option<bool>>(idx)?.map(Field::BooleanValue)
+    } else if *column_type == Type::INT2 {
+        row.try_get::<_, Option<i16>>(idx)?.map(|value| Field::LongValue(value as i64))
+    } else if *column_type == Type::INT4 {
+        row.try_get::<_, Option<i32>>(idx)?.map(|value| Field::LongValue(value as i64))
+    } else if *column_type == Type::INT8 {
+        row.try_get::<_, Option<i64>>(idx)?.map(|value| long_field(value, options.long_return_type))
+    } else if *column_type == Type::OID {
+        row.try_get::<_, Option<u32>>(idx)?.map(|value| Field::LongValue(value as i64))
+    } else if *column_type == Type::FLOAT4 {
+        row.try_get::<_, Option<f32>>(idx)?.map(|value| Field::DoubleValue(value as f64))
+    } else if *column_type == Type::FLOAT8 {
+        row.try_get::<_, Option<f64>>(idx)?.map(Field::DoubleValue)
+    } else if *column_type == Type::NUMERIC {
+        row.try_get::<_, Option<PgNumeric>>(idx)?.map(|value| decimal_field(value.0, options.decimal_return_type))
+    } else if *column_type == Type::UUID {
+        row.try_get::<_, Option<PgUuid>>(idx)?.map(|value| Field::StringValue(value.0))
+    } else if *column_type == Type::DATE {
+        row.try_get::<_, Option<NaiveDate>>(idx)?.map(|value| Field::StringValue(value.format("%Y-%m-%d").to_string()))
+    } else if *column_type == Type::TIME {
+        row.try_get::<_, Option<NaiveTime>>(idx)?.map(|value| Field::StringValue(value.format("%H:%M:%S%.f").to_string()))
+    } else if *column_type == Type::TIMESTAMP {
+        row.try_get::<_, Option<NaiveDateTime>>(idx)?.map(|value| Field::StringValue(value.format("%Y-%m-%d %H:%M:%S%.f").to_string()))
+    } else if *column_type == Type::TIMESTAMPTZ {
+        row.try_get::<_, Option<DateTime<Utc>>>(idx)?.map(|value| Field::StringValue(value.format("%Y-%m-%d %H:%M:%S%.f").to_string()))
+    } else if *column_type == Type::JSON || *column_type == Type::JSONB {
+        row.try_get::<_, Option<serde_json::Value>>(idx)?.map(|value| Field::StringValue(value.to_string()))
+    } else if *column_type == Type::BYTEA {
+        row.try_get::<_, Option<Vec<u8>>>(idx)?.map(|value| Field::BlobValue(base64::encode(value)))
+    } else if <String as FromSql>::accepts(column_type) {
+        row.try_get::<_, Option<String>>(idx)?.map(Field::StringValue)
+    } else if let Kind::Array(element_type) = column_type.kind() {
+        format_pg_array(row, idx, element_type)?.map(Field::ArrayValue)
+    } else {
+        return Err(Error {
+            msg: format!("not implemented conversion of postgres type {}", column_type.name()),
+            error_type: ErrorType::UnsupportedResultException,
+        });
+    };
+    Ok(field.unwrap_or(Field::IsNull(true)))
+}
+
+// arrays of the scalar types above, one with more dimensions nests arrayValues
+fn format_pg_array(row: &Row, idx: usize, element_type: &Type) -> Result<Option<ArrayValue>, Error> {
+    let array_value = if *element_type == Type::BOOL {
+        get_pg_array(row, idx, ArrayValue::BooleanValues)?
+    } else if *element_type == Type::INT2 {
+        get_pg_array(row, idx, |values: Vec<i16>| ArrayValue::LongValues(values.into_iter().map(i64::from).collect()))?
+    } else if *element_type == Type::INT4 {
+        get_pg_array(row, idx, |values: Vec<i32>| ArrayValue::LongValues(values.into_iter().map(i64::from).collect()))?
+    } else if *element_type == Type::INT8 {
+        get_pg_array(row, idx, ArrayValue::LongValues)?
+    } else if *element_type == Type::FLOAT4 {
+        get_pg_array(row, idx, |values: Vec<f32>| ArrayValue::DoubleValues(values.into_iter().map(f64::from).collect()))?
+    } else if *element_type == Type::FLOAT8 {
+        get_pg_array(row, idx, ArrayValue::DoubleValues)?
+    } else if *element_type == Type::NUMERIC {
+        get_pg_array(row, idx, |values: Vec<PgNumeric>| ArrayValue::StringValues(values.into_iter().map(|value| value.0).collect()))?
+    } else if *element_type == Type::UUID {
+        get_pg_array(row, idx, |values: Vec<PgUuid>| ArrayValue::StringValues(values.into_iter().map(|value| value.0).collect()))?
+    } else if <String as FromSql>::accepts(element_type) {
+        get_pg_array(row, idx, ArrayValue::StringValues)?
+    } else {
+        return Err(Error {
+            msg: format!("not implemented conversion of postgres type {}[]", element_type.name()),
+            error_type: ErrorType::UnsupportedResultException,
+        });
+    };
+    Ok(array_value)
+}
+
+// arrayValue has no way to say null, an array with null elements cannot be returned
+fn get_pg_array<'a, T: FromSql<'a>>(row: &'a Row, idx: usize, values: impl Fn(Vec<T>) -> ArrayValue) -> Result<Option<ArrayValue>, Error> {
+    match row.try_get::<_, Option<PgArray<T>>>(idx)? {
+        Some(array) => match array.elements.into_iter().collect::<Option<Vec<T>>>() {
+            Some(elements) => Ok(Some(nest_pg_array(&array.dimensions, &mut elements.into_iter(), &values))),
+            None => Err(Error {
+                msg: "not implemented conversion of array with null elements".to_owned(),
+                error_type: ErrorType::UnsupportedResultException,
+            }),
+        },
+        None => Ok(None),
+    }
+}
+
+// the elements come in row-major order, every dimension but the last is an arrayValues of the next
+fn nest_pg_array<T>(dimensions: &[usize], elements: &mut impl Iterator<Item = T>, values: &impl Fn(Vec<T>) -> ArrayValue) -> ArrayValue {
+    match dimensions {
+        [size, inner_dimensions @ ..] if !inner_dimensions.is_empty() => {
+            ArrayValue::ArrayValues((0..*size).map(|_| nest_pg_array(inner_dimensions, elements, values)).collect())
+        },
+        // an empty array has no dimension at all
+        _ => values(elements.take(dimensions.first().copied().unwrap_or(0)).collect()),
+    }
+}
+
+// java.sql.Types of a column, what the pgjdbc driver behind the data api reports
+fn jdbc_type(column_type: &Type) -> i64 {
+    let jdbc_types: [(Type, i64); 17] = [
+        (Type::BOOL, -7),
+        (Type::INT2, 5),
+        (Type::INT4, 4),
+        (Type::OID, 4),
+        (Type::INT8, -5),
+        (Type::FLOAT4, 7),
+        (Type::FLOAT8, 8),
+        (Type::NUMERIC, 2),
+        (Type::BPCHAR, 1),
+        (Type::VARCHAR, 12),
+        (Type::TEXT, 12),
+        (Type::NAME, 12),
+        (Type::DATE, 91),
+        (Type::TIME, 92),
+        (Type::TIMESTAMP, 93),
+        (Type::TIMESTAMPTZ, 93),
+        (Type::BYTEA, -2),
+    ];
+    match jdbc_types.iter().find(|(ty, _)| ty == column_type) {
+        Some((_, jdbc_type)) => *jdbc_type,
+        None => match column_type.kind() {
+            Kind::Array(_) => 2003,
+            _ => 1111,
+        },
+    }
+}
+
+fn format_pg_result(columns: &[Column], rows: &[Row], options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+    let mut records: Vec<Vec<Field>> = Vec::with_capacity(rows.len());
+    let mut response_size = ResponseSize::new(options);
+    let mut column_metadata: Vec<ColumnMetadata> = Vec::new();
+    if options.include_result_metadata {
+        for column in columns {
+            column_metadata.push(ColumnMetadata {
+                array_base_column_type: Some(match column.type_().kind() {
+                    Kind::Array(element_type) => jdbc_type(element_type),
+                    _ => 0,
+                }),
+                is_auto_increment: None,
+                is_case_sensitive: None,
+                is_currency: None,
+                is_signed: None,
+                label: Some(column.name().to_string()),
+                name: Some(column.name().to_string()),
+                nullable: None,
+                precision: None,
+                scale: None,
+                schema_name: None,
+                table_name: None,
+                type_: Some(jdbc_type(column.type_())),
+                type_name: Some(column.type_().name().to_string()),
+            });
+        }
+    }
+    for row in rows {
+        let mut record: Vec<Field> = Vec::with_capacity(columns.len());
+        for (idx, column) in columns.iter().enumerate() {
+            record.push(format_pg_value(row, idx, column.type_(), options)?);
+        }
+        response_size.add(&record)?;
+        records.push(record);
+    }
+    let labels: Vec<String> = columns.iter().map(|column| column.name().to_string()).collect();
+    let (records, formatted_records) = format_records(records, &labels, options);
+    Ok(ExecuteStatementResponse {
+        number_of_records_updated: 0,
+        generated_fields: None,
+        records: records,
+        column_metadata: Some(column_metadata),
+        formatted_records: formatted_records,
+    })
+}
@@ -0,0 +1,259 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use rusqlite::types::{ToSql, Value as SqliteValue, ValueRef as SqliteValueRef};
+
+use super::{blocking, decimal_field, decode_blob, format_records, long_field, parse_type_hint, run_locked, unsupported_parameter_error, with_statement_timeout, Backend, Connection, ExecuteOptions, ResponseSize};
+use crate::config::{ClusterConfig, SecretConfig};
+use crate::model::{ColumnMetadata, Error, ErrorType, ExecuteStatementResponse, Field, SqlParameter, UpdateResult};
+
+pub struct SqliteBackend {
+    path: String,
+    database: String,
+}
+
+impl SqliteBackend {
+    pub fn new(cluster: &ClusterConfig) -> SqliteBackend {
+        SqliteBackend {
+            path: cluster.path.clone().unwrap_or(".".to_owned()),
+            database: cluster.database(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Backend for SqliteBackend {
+    // every database is its own file under the cluster path, created on first use
+    // sqlite has no users, every secret gets full access
+    async fn connect(&self, _secret: &SecretConfig, database: Option<String>, schema: Option<String>) -> Result<Box<dyn Connection>, Error> {
+        check_schema(schema)?;
+        let sqlite_database: String = match database {
+            Some(db) => {
+                check_database_name(&db)?;
+                db
+            },
+            None => self.database.clone(),
+        };
+        let file = Path::new(&self.path).join(format!("{}.sqlite", sqlite_database));
+        let conn = blocking(move || {
+            let conn = rusqlite::Connection::open(file)?;
+            // transactions hold the write lock, wait for them instead of failing with SQLITE_BUSY
+            conn.busy_timeout(Duration::from_secs(5))?;
+            Ok(conn)
+        }).await?;
+        Ok(Box::new(SqliteConnection {
+            interrupt: conn.get_interrupt_handle(),
+            conn: Arc::new(Mutex::new(conn)),
+        }))
+    }
+}
+
+// the database is a file name under the cluster path, it cannot point anywhere else
+fn check_database_name(database: &str) -> Result<(), Error> {
+    if database.is_empty() || database.contains(['/', '\\', '\0']) || database.contains("..") {
+        return Err(Error {
+            msg: format!("Invalid database name '{}', it has to be a file name without '/', '\\' or '..'", database),
+            error_type: ErrorType::BadRequestException,
+        });
+    }
+    Ok(())
+}
+
+// sqlite has no schema apart from the database, like aurora mysql
+fn check_schema(schema: Option<String>) -> Result<(), Error> {
+    if schema.is_some_and(|schema| !schema.is_empty()) {
+        return Err(Error {
+            msg: "The schema parameter isn't supported for SQLite, use database instead".to_owned(),
+            error_type: ErrorType::BadRequestException,
+        });
+    }
+    Ok(())
+}
+
+pub struct SqliteConnection {
+    // shared with the statement that is still running after a timeout with continueAfterTimeout
+    conn: Arc<Mutex<rusqlite::Connection>>,
+    interrupt: rusqlite::InterruptHandle,
+}
+
+impl SqliteConnection {
+    // sqlite blocks on the file lock, every call runs on the blocking thread pool and waits there for a statement still running
+    async fn run<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&rusqlite::Connection) -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        run_locked(&self.conn, None, move |conn| f(conn)).await
+    }
+}
+
+#[async_trait(?Send)]
+impl Connection for SqliteConnection {
+    async fn begin(&mut self) -> Result<(), Error> {
+        self.run(|conn| {
+            conn.execute_batch("BEGIN")?;
+            Ok(())
+        }).await
+    }
+    // a sqlite connection is bound to its file, the database is chosen on connect
+    async fn select_database_and_schema(&mut self, _database: Option<String>, schema: Option<String>) -> Result<(), Error> {
+        check_schema(schema)
+    }
+    async fn execute(&mut self, sql: &str, parameters: Vec<SqlParameter>, options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+        let sql = sql.to_owned();
+        let statement_options = options.clone();
+        let (started_tx, started) = oneshot::channel();
+        let statement = run_locked(&self.conn, Some(started_tx), move |conn| execute(conn, &sql, parameters, &statement_options));
+        let cancel = async {
+            self.interrupt.interrupt();
+            Ok(())
+        };
+        with_statement_timeout(options, started, statement, cancel).await
+    }
+    async fn batch_execute(&mut self, sql: &str, parameter_sets: Vec<Vec<SqlParameter>>) -> Result<Vec<UpdateResult>, Error> {
+        let sql = sql.to_owned();
+        self.run(move |conn| {
+            let mut update_results: Vec<UpdateResult> = Vec::with_capacity(parameter_sets.len());
+            for parameters in parameter_sets {
+                let result = execute(conn, &sql, parameters, &ExecuteOptions::default())?;
+                update_results.push(UpdateResult {
+                    generated_fields: result.generated_fields.unwrap_or_default(),
+                });
+            }
+            Ok(update_results)
+        }).await
+    }
+    async fn commit(&mut self) -> Result<(), Error> {
+        self.run(|conn| {
+            conn.execute_batch("COMMIT")?;
+            Ok(())
+        }).await
+    }
+    async fn rollback(&mut self) -> Result<(), Error> {
+        self.run(|conn| {
+            conn.execute_batch("ROLLBACK")?;
+            Ok(())
+        }).await
+    }
+    // an interrupted write rolls the whole transaction back, a statement still running keeps it open
+    fn is_in_transaction(&self) -> bool {
+        self.conn.try_lock().map_or(true, |conn| !conn.is_autocommit())
+    }
+}
+
+fn to_sqlite_value(parameter: SqlParameter) -> Result<SqliteValue, Error> {
+    let sqlite_value = match parameter.value {
+        Field::ArrayValue(_) => return Err(unsupported_parameter_error("Array")),
+        Field::StructValue(_) => return Err(unsupported_parameter_error("Struct")),
+        Field::BlobValue(value) => SqliteValue::Blob(decode_blob(&parameter.name, &value)?),
+        Field::BooleanValue(value) => SqliteValue::Integer(if value { 1 } else { 0 }),
+        Field::DoubleValue(value) => SqliteValue::Real(value),
+        Field::IsNull(_) => SqliteValue::Null,
+        Field::LongValue(value) => SqliteValue::Integer(value),
+        Field::StringValue(value) => SqliteValue::Text(value),
+    };
+    Ok(sqlite_value)
+}
+
+// REPLACE is sqlite shorthand for INSERT OR REPLACE
+fn is_insert(sql: &str) -> bool {
+    let verb = sql.split_whitespace().next().unwrap_or_default().to_uppercase();
+    verb == "INSERT" || verb == "REPLACE"
+}
+
+fn execute(conn: &rusqlite::Connection, sql: &str, parameters: Vec<SqlParameter>, options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+    let mut stmt = conn.prepare(sql)?;
+    // sqlite understands :name itself, parameters that the sql does not use are ignored like in mysql
+    let mut named_values: Vec<(String, SqliteValue)> = Vec::with_capacity(parameters.len());
+    for parameter in parameters {
+        let param_name = format!(":{}", parameter.name);
+        if stmt.parameter_index(&param_name)?.is_some() {
+            // sqlite has no temporal or uuid types, a valid typeHint value is stored as the text it came as
+            if let (Field::StringValue(value), Some(type_hint)) = (&parameter.value, parameter.type_hint) {
+                parse_type_hint(&parameter.name, value, type_hint)?;
+            }
+            named_values.push((param_name, to_sqlite_value(parameter)?));
+        }
+    }
+    let params: Vec<(&str, &dyn ToSql)> = named_values.iter()
+        .map(|(name, value)| (name.as_str(), value as &dyn ToSql))
+        .collect();
+    if stmt.column_count() == 0 {
+        let number_of_records_updated = stmt.execute_named(&params)?;
+        // last_insert_rowid is only the row of this statement when it is an INSERT that added one
+        let generated_fields: Option<Vec<Field>> = if is_insert(sql) && number_of_records_updated > 0 {
+            Some(vec![Field::LongValue(conn.last_insert_rowid())])
+        } else {
+            None
+        };
+        let (records, formatted_records) = format_records(Vec::new(), &[], options);
+        return Ok(ExecuteStatementResponse {
+            number_of_records_updated: number_of_records_updated as u64,
+            generated_fields: generated_fields,
+            records: records,
+            column_metadata: Some(Vec::new()),
+            formatted_records: formatted_records,
+        });
+    }
+    let mut column_metadata: Vec<ColumnMetadata> = Vec::new();
+    // sqlite values carry no declared type, the column declaration decides booleans and resultSetOptions
+    let mut decl_types: Vec<String> = Vec::with_capacity(stmt.column_count());
+    let labels: Vec<String> = stmt.column_names().iter().map(|name| name.to_string()).collect();
+    for column in stmt.columns() {
+        let decl_type = column.decl_type().map(|decl_type| decl_type.to_uppercase());
+        decl_types.push(decl_type.clone().unwrap_or_default());
+        if options.include_result_metadata {
+            column_metadata.push(ColumnMetadata {
+                array_base_column_type: Some(0),
+                is_auto_increment: None,
+                is_case_sensitive: None,
+                is_currency: None,
+                is_signed: None,
+                label: Some(column.name().to_string()),
+                name: Some(column.name().to_string()),
+                nullable: None,
+                precision: None,
+                scale: None,
+                schema_name: None,
+                table_name: None,
+                type_: None,
+                type_name: decl_type,
+            });
+        }
+    }
+    let mut records: Vec<Vec<Field>> = Vec::new();
+    let mut response_size = ResponseSize::new(options);
+    let mut rows = stmt.query_named(&params)?;
+    while let Some(row) = rows.next()? {
+        let mut record: Vec<Field> = Vec::with_capacity(decl_types.len());
+        for (i, decl_type) in decl_types.iter().enumerate() {
+            let is_boolean = decl_type == "BOOLEAN" || decl_type == "BOOL";
+            let is_decimal = decl_type.starts_with("DECIMAL") || decl_type.starts_with("NUMERIC");
+            let field = match row.get_raw(i) {
+                SqliteValueRef::Null => Field::IsNull(true),
+                SqliteValueRef::Integer(value) if is_boolean => Field::BooleanValue(value != 0),
+                SqliteValueRef::Integer(value) if is_decimal => decimal_field(value.to_string(), options.decimal_return_type),
+                SqliteValueRef::Integer(value) if decl_type == "BIGINT" => long_field(value, options.long_return_type),
+                SqliteValueRef::Integer(value) => Field::LongValue(value),
+                SqliteValueRef::Real(value) if is_decimal => decimal_field(value.to_string(), options.decimal_return_type),
+                SqliteValueRef::Real(value) => Field::DoubleValue(value),
+                SqliteValueRef::Text(value) => Field::StringValue(String::from_utf8_lossy(value).to_string()),
+                SqliteValueRef::Blob(value) => Field::BlobValue(base64::encode(value)),
+            };
+            record.push(field);
+        }
+        response_size.add(&record)?;
+        records.push(record);
+    }
+    let (records, formatted_records) = format_records(records, &labels, options);
+    Ok(ExecuteStatementResponse {
+        number_of_records_updated: 0,
+        generated_fields: None,
+        records: records,
+        column_metadata: Some(column_metadata),
+        formatted_records: formatted_records,
+    })
+}
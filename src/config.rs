@@ -0,0 +1,146 @@
+use std::env;
+use std::fs;
+use std::str::FromStr;
+
+// database credentials behind a secretArn, missing user/password fall back to the cluster ones
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecretConfig {
+    pub arn: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+// one emulated Aurora cluster, requests are routed to it by resourceArn
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterConfig {
+    pub name: Option<String>,
+    #[serde(rename="resourceArn")]
+    pub resource_arn: String,
+    // secrets that connect with the cluster user/password
+    #[serde(default)]
+    #[serde(rename="secretArns")]
+    pub secret_arns: Vec<String>,
+    // secrets that connect with their own user/password
+    #[serde(default)]
+    pub secrets: Vec<SecretConfig>,
+    pub engine: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    // used when the request has no database
+    pub database: Option<String>,
+    // directory of the database files, sqlite only
+    pub path: Option<String>,
+    // aurora serverless auto-pause, the cluster never pauses without it
+    #[serde(rename="autoPauseSeconds")]
+    pub auto_pause_seconds: Option<u64>,
+    #[serde(rename="resumeSeconds")]
+    pub resume_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub clusters: Vec<ClusterConfig>,
+}
+
+impl Config {
+    // CLUSTERS_CONFIG points to a json file, without it the single cluster from the environment is used
+    pub fn load() -> Result<Config, String> {
+        match env::var("CLUSTERS_CONFIG") {
+            Ok(path) => {
+                let content = fs::read_to_string(&path).map_err(|err| format!("cannot read CLUSTERS_CONFIG {}: {}", path, err))?;
+                serde_json::from_str(&content).map_err(|err| format!("invalid CLUSTERS_CONFIG {}: {}", path, err))
+            },
+            Err(_) => Ok(Config {
+                clusters: vec![ClusterConfig::from_env()?],
+            }),
+        }
+    }
+}
+
+impl ClusterConfig {
+    pub fn engine(&self) -> String {
+        self.engine.clone().unwrap_or("mysql".to_owned()).to_lowercase()
+    }
+    pub fn host(&self) -> String {
+        self.host.clone().unwrap_or("localhost".to_owned())
+    }
+    pub fn port(&self) -> u16 {
+        match self.port {
+            Some(port) => port,
+            None if self.engine() == "postgres" => 5432,
+            None => 3306,
+        }
+    }
+    pub fn database(&self) -> String {
+        match self.database {
+            Some(ref database) => database.clone(),
+            None if self.engine() == "postgres" => "postgres".to_owned(),
+            None if self.engine() == "sqlite" => "main".to_owned(),
+            None => "mysql".to_owned(),
+        }
+    }
+    pub fn resume_seconds(&self) -> u64 {
+        self.resume_seconds.unwrap_or(30)
+    }
+    fn default_user(&self) -> String {
+        if self.engine() == "postgres" {
+            "postgres".to_owned()
+        } else {
+            "root".to_owned()
+        }
+    }
+    // the secret is returned with the user/password the emulator connects with
+    pub fn secret(&self, secret_arn: &str) -> Option<SecretConfig> {
+        let (user, password) = match self.secrets.iter().find(|secret| secret.arn == secret_arn) {
+            Some(secret) => (secret.user.clone().or(self.user.clone()), secret.password.clone().or(self.password.clone())),
+            None if self.secret_arns.iter().any(|arn| arn == secret_arn) => (self.user.clone(), self.password.clone()),
+            None => return None,
+        };
+        Some(SecretConfig {
+            arn: secret_arn.to_owned(),
+            user: Some(user.unwrap_or(self.default_user())),
+            password: Some(password.unwrap_or(String::new())),
+        })
+    }
+    fn from_env() -> Result<ClusterConfig, String> {
+        let engine = env::var("ENGINE").unwrap_or("mysql".to_owned()).to_lowercase();
+        let prefix = if engine == "postgres" {
+            "POSTGRES"
+        } else {
+            "MYSQL"
+        };
+        Ok(ClusterConfig {
+            name: None,
+            resource_arn: require_env("RESOURCE_ARN")?,
+            secret_arns: vec![require_env("SECRET_ARN")?],
+            secrets: Vec::new(),
+            host: env::var(format!("{}_HOST", prefix)).ok(),
+            port: parse_env(&format!("{}_PORT", prefix))?,
+            user: env::var(format!("{}_USER", prefix)).ok(),
+            password: env::var(format!("{}_PASSWORD", prefix)).ok(),
+            database: if engine == "sqlite" {
+                env::var("SQLITE_DATABASE").ok()
+            } else {
+                env::var(format!("{}_DATABASE", prefix)).ok()
+            },
+            path: env::var("SQLITE_PATH").ok(),
+            auto_pause_seconds: parse_env("AUTO_PAUSE_SECONDS")?,
+            resume_seconds: parse_env("RESUME_SECONDS")?,
+            engine: Some(engine),
+        })
+    }
+}
+
+pub fn require_env(name: &str) -> Result<String, String> {
+    env::var(name).map_err(|_| format!("{} is not set", name))
+}
+
+// a variable that is set has to parse, a typo should not silently fall back to the default
+pub fn parse_env<T: FromStr>(name: &str) -> Result<Option<T>, String> {
+    match env::var(name) {
+        Ok(value) => value.parse::<T>().map(Some).map_err(|_| format!("invalid {}: {}", name, value)),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,425 @@
+// struct literals spell out `field: field` throughout
+#![allow(clippy::redundant_field_names)]
+
+extern crate rand;
+
+#[macro_use]
+extern crate actix_web;
+
+#[macro_use]
+extern crate serde_derive;
+
+pub mod backend;
+pub mod config;
+pub mod model;
+mod pause;
+
+use crate::backend::{get_backend, Backend, Connection, ExecuteOptions};
+use crate::config::{ClusterConfig, SecretConfig};
+use crate::pause::AutoPause;
+use futures::lock::Mutex;
+use crate::rand::Rng;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use actix_web::{
+    guard, web, HttpRequest, HttpResponse,
+};
+use crate::model::{GetSecretValueRequest, GetSecretValueResponse, DatabaseSecret, TransactionStatus, Field, BeginTransactionRequest,BeginTransactionResponse,CommitTransactionRequest,CommitTransactionResponse,RollbackTransactionRequest,RollbackTransactionResponse,ExecuteStatementRequest,BatchExecuteStatementRequest,UpdateResult,BatchExecuteStatementResponse,Error,ErrorType};
+
+struct CheckArnParam {
+    resource_arn: String,
+    secret_arn: String,
+}
+fn check_arn(clusters: &[Cluster], check_arn: CheckArnParam) -> Result<(&Cluster, SecretConfig), Error> {
+    let cluster = match clusters.iter().find(|cluster| cluster.config.resource_arn == check_arn.resource_arn) {
+        Some(cluster) => cluster,
+        None => return Err(Error {
+            msg: format!("HttpEndPoint is not enabled for {}", check_arn.resource_arn),
+            error_type: ErrorType::HttpEndpointNotEnabledException,
+        }),
+    };
+    if let Some(secret) = cluster.config.secret(&check_arn.secret_arn) {
+        return Ok((cluster, secret));
+    }
+    // a secret of another cluster is invalid for this one, a secret nobody knows cannot be fetched at all
+    if clusters.iter().any(|cluster| cluster.config.secret(&check_arn.secret_arn).is_some()) {
+        Err(Error {
+            msg: format!("The secret {} is not valid for {}", check_arn.secret_arn, check_arn.resource_arn),
+            error_type: ErrorType::InvalidSecretException,
+        })
+    } else {
+        Err(Error {
+            msg: format!("Secrets Manager can't find the specified secret {}", check_arn.secret_arn),
+            error_type: ErrorType::SecretsErrorException,
+        })
+    }
+}
+
+fn transaction_not_found(transaction_id: &str) -> Error {
+    Error {
+        msg: format!("Transaction {} is not found", transaction_id),
+        error_type: ErrorType::TransactionNotFoundException,
+    }
+}
+
+// a transaction can only be found through the cluster it was started on and only used with the secret it was started with
+fn find_transaction<'a>(connections: &'a mut HashMap<String, Transaction>, transaction_id: &str, resource_arn: &str, secret_arn: &str) -> Result<&'a mut Transaction, Error> {
+    match connections.get_mut(transaction_id) {
+        Some(transaction) if transaction.resource_arn == resource_arn && transaction.secret_arn == secret_arn => Ok(transaction),
+        Some(transaction) if transaction.resource_arn == resource_arn => Err(Error {
+            msg: format!("Transaction {} was not started with the secret {}", transaction_id, secret_arn),
+            error_type: ErrorType::BadRequestException,
+        }),
+        _ => Err(transaction_not_found(transaction_id)),
+    }
+}
+
+// the map is only locked to look the transaction up, its statement runs under the lock of the transaction alone
+async fn use_transaction(connections: &Mutex<HashMap<String, Transaction>>, transaction_id: &str, resource_arn: &str, secret_arn: &str) -> Result<SharedConnection, Error> {
+    let mut connections = connections.lock().await;
+    let transaction = find_transaction(&mut connections, transaction_id, resource_arn, secret_arn)?;
+    transaction.last_used_at = Instant::now();
+    Ok(transaction.conn.clone())
+}
+
+// commit and rollback end the transaction, it is forgotten before they run so no other statement can join it
+async fn take_transaction(connections: &Mutex<HashMap<String, Transaction>>, transaction_id: &str, resource_arn: &str, secret_arn: &str) -> Result<Transaction, Error> {
+    let mut connections = connections.lock().await;
+    find_transaction(&mut connections, transaction_id, resource_arn, secret_arn)?;
+    connections.remove(transaction_id).ok_or_else(|| transaction_not_found(transaction_id))
+}
+
+// a transaction the database ended by itself is forgotten, later calls fail with "Transaction ... is not found" like after a rollback
+async fn forget_ended_transaction(connections: &Mutex<HashMap<String, Transaction>>, transaction_id: &str, conn: &dyn Connection) {
+    if !conn.is_in_transaction() {
+        connections.lock().await.remove(transaction_id);
+    }
+}
+
+// counts as activity for auto-pause, a cluster with a transaction open on it is never idle
+async fn wake_cluster(cluster: &Cluster, connections: &Mutex<HashMap<String, Transaction>>) -> Result<(), Error> {
+    let has_open_transactions = connections.lock().await.values().any(|transaction| transaction.resource_arn == cluster.config.resource_arn);
+    cluster.pause.wake(&cluster.config.resource_arn, has_open_transactions)
+}
+
+#[post("/BeginTransaction")]
+async fn begin_transaction_statement(begin_transaction_request_wj: web::Json<BeginTransactionRequest>, app_data: web::Data<AppData>) -> Result<HttpResponse, Error> {
+    let begin_transaction_request = begin_transaction_request_wj.into_inner();
+    let (cluster, secret) = check_arn(&app_data.clusters, CheckArnParam {
+        resource_arn: begin_transaction_request.resource_arn,
+        secret_arn: begin_transaction_request.secret_arn,
+    })?;
+    wake_cluster(cluster, &app_data.connections).await?;
+    let transaction_id = create_transaction_id();
+    let begin_transaction_response = BeginTransactionResponse {
+        transaction_id: transaction_id.clone()
+    };
+    let mut conn = cluster.backend.connect(&secret, begin_transaction_request.database, begin_transaction_request.schema).await?;
+    conn.begin().await?;
+    let mut connections = app_data.connections.lock().await;
+    connections.insert(transaction_id.clone(), Transaction {
+        resource_arn: cluster.config.resource_arn.clone(),
+        secret_arn: secret.arn.clone(),
+        conn: Arc::new(Mutex::new(conn)),
+        started_at: Instant::now(),
+        last_used_at: Instant::now(),
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .json(begin_transaction_response))
+}
+
+#[post("/CommitTransaction")]
+async fn commit_transaction_statement(commit_transaction_request_wj: web::Json<CommitTransactionRequest>, app_data: web::Data<AppData>) -> Result<HttpResponse, Error> {
+    let commit_transaction_request = commit_transaction_request_wj.into_inner();
+    let (cluster, secret) = check_arn(&app_data.clusters, CheckArnParam {
+        resource_arn: commit_transaction_request.resource_arn,
+        secret_arn: commit_transaction_request.secret_arn,
+    })?;
+    wake_cluster(cluster, &app_data.connections).await?;
+    let transaction = take_transaction(&app_data.connections, &commit_transaction_request.transaction_id, &cluster.config.resource_arn, &secret.arn).await?;
+    transaction.conn.lock().await.commit().await?;
+    Ok(HttpResponse::Ok()
+        .json(CommitTransactionResponse {
+            transaction_status: TransactionStatus::TransactionCommitted,
+        }))
+}
+
+#[post("/RollbackTransaction")]
+async fn rollback_transaction_statement(rollback_transaction_request_wj: web::Json<RollbackTransactionRequest>, app_data: web::Data<AppData>) ->  Result< HttpResponse, Error> {
+    let rollback_transaction_request = rollback_transaction_request_wj.into_inner();
+    let (cluster, secret) = check_arn(&app_data.clusters, CheckArnParam {
+        resource_arn: rollback_transaction_request.resource_arn,
+        secret_arn: rollback_transaction_request.secret_arn,
+    })?;
+    wake_cluster(cluster, &app_data.connections).await?;
+    let transaction = take_transaction(&app_data.connections, &rollback_transaction_request.transaction_id, &cluster.config.resource_arn, &secret.arn).await?;
+    transaction.conn.lock().await.rollback().await?;
+    Ok(HttpResponse::Ok()
+        .json(RollbackTransactionResponse {
+            transaction_status: TransactionStatus::RollbackComplete,
+        }))
+}
+#[post("/Execute")]
+async fn execute_statement(execute_transaction_request_wj: web::Json<ExecuteStatementRequest>, app_data: web::Data<AppData>) ->  Result<HttpResponse, Error> {
+    let execute_transaction_request = execute_transaction_request_wj.into_inner();
+    let (cluster, secret) = check_arn(&app_data.clusters, CheckArnParam {
+        resource_arn: execute_transaction_request.resource_arn,
+        secret_arn: execute_transaction_request.secret_arn,
+    })?;
+    wake_cluster(cluster, &app_data.connections).await?;
+
+    let options = ExecuteOptions {
+        include_result_metadata: execute_transaction_request.include_result_metadata.unwrap_or(false),
+        timeout: app_data.statement_timeout,
+        continue_after_timeout: execute_transaction_request.continue_after_timeout.unwrap_or(false),
+        response_size_limit: Some(app_data.response_size_limit),
+        format_records_as: execute_transaction_request.format_records_as.unwrap_or_default(),
+        decimal_return_type: execute_transaction_request.result_set_options.as_ref().and_then(|result_set_options| result_set_options.decimal_return_type).unwrap_or_default(),
+        long_return_type: execute_transaction_request.result_set_options.as_ref().and_then(|result_set_options| result_set_options.long_return_type).unwrap_or_default(),
+    };
+    let parameters = execute_transaction_request.parameters.unwrap_or_default();
+    let exec_result = match execute_transaction_request.transaction_id {
+        None => {
+            let mut conn = cluster.backend.connect(&secret, execute_transaction_request.database, execute_transaction_request.schema).await?;
+            conn.execute(&execute_transaction_request.sql, parameters, &options).await
+        },
+        Some(transaction_id) => {
+            let conn = use_transaction(&app_data.connections, &transaction_id, &cluster.config.resource_arn, &secret.arn).await?;
+            let mut conn = conn.lock().await;
+            conn.select_database_and_schema(execute_transaction_request.database, execute_transaction_request.schema).await?;
+            let exec_result = conn.execute(&execute_transaction_request.sql, parameters, &options).await;
+            if exec_result.is_err() {
+                forget_ended_transaction(&app_data.connections, &transaction_id, &**conn).await;
+            }
+            exec_result
+        },
+    };
+    match exec_result {
+        Ok(some) => Ok(HttpResponse::Ok().json(some)),
+        Err(some_error) => Err(some_error),
+    }
+}
+#[post("/BatchExecute")]
+async fn batch_execute_statement(batch_execute_transaction_request_wj: web::Json<BatchExecuteStatementRequest>, app_data: web::Data<AppData>) ->  Result<HttpResponse, Error> {
+    let batch_execute_transaction_request = batch_execute_transaction_request_wj.into_inner();
+    let (cluster, secret) = check_arn(&app_data.clusters, CheckArnParam {
+        resource_arn: batch_execute_transaction_request.resource_arn,
+        secret_arn: batch_execute_transaction_request.secret_arn,
+    })?;
+    wake_cluster(cluster, &app_data.connections).await?;
+    // let include_result_metadata = match batch_execute_transaction_request.include_result_metadata {
+    //     Some(b) => b,
+    //     None => false,
+    // };
+
+    let parameter_sets = batch_execute_transaction_request.parameter_sets.unwrap_or_default();
+
+    let update_results: Vec<UpdateResult> = match batch_execute_transaction_request.transaction_id {
+        None => {
+            let mut conn = cluster.backend.connect(&secret, batch_execute_transaction_request.database, batch_execute_transaction_request.schema).await?;
+            conn.batch_execute(&batch_execute_transaction_request.sql, parameter_sets).await?
+        },
+        Some(transaction_id) => {
+            let conn = use_transaction(&app_data.connections, &transaction_id, &cluster.config.resource_arn, &secret.arn).await?;
+            let mut conn = conn.lock().await;
+            conn.select_database_and_schema(batch_execute_transaction_request.database, batch_execute_transaction_request.schema).await?;
+            let batch_result = conn.batch_execute(&batch_execute_transaction_request.sql, parameter_sets).await;
+            if batch_result.is_err() {
+                forget_ended_transaction(&app_data.connections, &transaction_id, &**conn).await;
+            }
+            batch_result?
+        },
+    };
+    Ok(HttpResponse::Ok().json(BatchExecuteStatementResponse {
+        update_results: update_results,
+    }))
+}
+
+// secretsmanager.GetSecretValue, aws json 1.1 is posted to / with the action in X-Amz-Target
+async fn get_secret_value(body: web::Bytes, app_data: web::Data<AppData>) -> Result<HttpResponse, Error> {
+    let get_secret_value_request: GetSecretValueRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(err) => return Err(Error {
+            msg: err.to_string(),
+            error_type: ErrorType::InvalidRequestException,
+        }),
+    };
+    // SecretId is either the full arn or the secret name
+    let secret_id = get_secret_value_request.secret_id;
+    for cluster in app_data.clusters.iter() {
+        let secret_arns = cluster.config.secret_arns.iter().chain(cluster.config.secrets.iter().map(|secret| &secret.arn));
+        for secret_arn in secret_arns {
+            let secret_name = secret_arn.split(":secret:").nth(1).unwrap_or(secret_arn);
+            if *secret_arn != secret_id && secret_name != secret_id {
+                continue;
+            }
+            let secret = match cluster.config.secret(secret_arn) {
+                Some(secret) => secret,
+                None => continue,
+            };
+            let database_secret = DatabaseSecret {
+                username: secret.user.unwrap_or_default(),
+                password: secret.password.unwrap_or_default(),
+                engine: cluster.config.engine(),
+                host: cluster.config.host(),
+                port: cluster.config.port(),
+                dbname: cluster.config.database(),
+                db_cluster_identifier: cluster.config.name.clone(),
+            };
+            let secret_string = serde_json::to_string(&database_secret).map_err(|err| Error {
+                msg: err.to_string(),
+                error_type: ErrorType::InternalServerErrorException,
+            })?;
+            return Ok(HttpResponse::Ok()
+                .content_type("application/x-amz-json-1.1")
+                .json(GetSecretValueResponse {
+                    arn: secret_arn.clone(),
+                    name: secret_name.to_owned(),
+                    version_id: "00000000-0000-0000-0000-000000000000".to_owned(),
+                    secret_string: secret_string,
+                    version_stages: vec!["AWSCURRENT".to_owned()],
+                    created_date: SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs_f64()).unwrap_or(0.0),
+                }))
+        }
+    }
+    Err(Error {
+        msg: "Secrets Manager can't find the specified secret.".to_owned(),
+        error_type: ErrorType::ResourceNotFoundException,
+    })
+}
+
+#[get("/")]
+async fn root_index(_req: HttpRequest) -> Result<HttpResponse, Error> {
+    let data = Field::StringValue(String::from("ok"));
+    Ok(
+    HttpResponse::Ok()
+    .json(data))
+}
+pub struct Cluster {
+    config: ClusterConfig,
+    backend: Arc<dyn Backend>,
+    pause: AutoPause,
+}
+impl Cluster {
+    // the backend of the configured engine
+    pub fn new(config: ClusterConfig) -> Cluster {
+        Cluster::with_backend(get_backend(&config), config)
+    }
+    // any backend, a stub in tests
+    pub fn with_backend(backend: Arc<dyn Backend>, config: ClusterConfig) -> Cluster {
+        Cluster {
+            backend: backend,
+            pause: AutoPause::new(&config),
+            config: config,
+        }
+    }
+}
+// a statement of the transaction holds the lock until it is done, the next one waits for it
+type SharedConnection = Arc<Mutex<Box<dyn Connection>>>;
+struct Transaction {
+    // a transaction can only be used through the cluster and the secret it was started with
+    resource_arn: String,
+    secret_arn: String,
+    conn: SharedConnection,
+    started_at: Instant,
+    last_used_at: Instant,
+}
+impl Transaction {
+    // a transaction whose statement is still running is not idle
+    fn is_expired(&self, now: Instant, idle_timeout: Duration, max_lifetime: Duration) -> bool {
+        let is_idle = now.duration_since(self.last_used_at) >= idle_timeout && self.conn.try_lock().is_some();
+        is_idle || now.duration_since(self.started_at) >= max_lifetime
+    }
+}
+#[derive(Clone)]
+pub struct AppData {
+    clusters: Arc<Vec<Cluster>>,
+    connections: Arc<Mutex<HashMap<String, Transaction>>>,
+    statement_timeout: Duration,
+    response_size_limit: usize,
+}
+impl AppData {
+    pub fn new(clusters: Vec<Cluster>, statement_timeout: Duration, response_size_limit: usize) -> AppData {
+        AppData {
+            clusters: Arc::new(clusters),
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            statement_timeout: statement_timeout,
+            response_size_limit: response_size_limit,
+        }
+    }
+}
+
+// aurora rolls back a transaction after 3 minutes without a statement or after 24 hours in total,
+// the id is forgotten so later calls fail with "Transaction ... is not found"
+pub async fn reap_transactions(app_data: AppData, idle_timeout: Duration, max_lifetime: Duration) {
+    let AppData { clusters, connections, .. } = app_data;
+    loop {
+        actix_rt::time::delay_for(Duration::from_secs(1)).await;
+        let expired_transactions: Vec<(String, Transaction)> = {
+            let mut connections = connections.lock().await;
+            let now = Instant::now();
+            let expired_ids: Vec<String> = connections.iter()
+                .filter(|(_, transaction)| transaction.is_expired(now, idle_timeout, max_lifetime))
+                .map(|(transaction_id, _)| transaction_id.clone())
+                .collect();
+            expired_ids.into_iter()
+                .filter_map(|transaction_id| connections.remove(&transaction_id).map(|transaction| (transaction_id, transaction)))
+                .collect()
+        };
+        // rolled back once the map is unlocked, requests for other transactions do not wait for it
+        for (transaction_id, transaction) in expired_transactions {
+            log::info!("rolling back expired transaction {}", transaction_id);
+            if let Err(err) = transaction.conn.lock().await.rollback().await {
+                log::warn!("rollback of expired transaction {} failed: {}", transaction_id, err.msg);
+            }
+            // auto-pause counts the idle time from the end of the last transaction
+            if let Some(cluster) = clusters.iter().find(|cluster| cluster.config.resource_arn == transaction.resource_arn) {
+                cluster.pause.touch();
+            }
+        }
+    }
+}
+
+fn create_transaction_id() -> String {
+    const TRANSACTION_ID_CHARACTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                            abcdefghijklmnopqrstuvwxyz\
+                            /=+";
+    const TRANSACTION_ID_LENGTH: usize = 184;
+    let mut rng = rand::thread_rng();
+    (0..TRANSACTION_ID_LENGTH)
+        .map(|_| {
+            let idx = rng.gen_range(0, TRANSACTION_ID_CHARACTERS.len());
+            TRANSACTION_ID_CHARACTERS[idx] as char
+        })
+        .collect()
+}
+
+// malformed requests are reported like any other BadRequestException
+pub fn json_config(limit: usize) -> web::JsonConfig {
+    web::JsonConfig::default()
+        .limit(limit)
+        .error_handler(|err, _req| Error {
+            msg: err.to_string(),
+            error_type: ErrorType::BadRequestException,
+        }.into())
+}
+
+// the data api and secrets manager routes, the AppData is registered by the caller
+pub fn configure(config: &mut web::ServiceConfig) {
+    config
+        .service(root_index)
+        .service(
+            web::resource("/")
+                .guard(guard::Header("x-amz-target", "secretsmanager.GetSecretValue"))
+                .route(web::post().to(get_secret_value)),
+        )
+        .service(begin_transaction_statement)
+        .service(commit_transaction_statement)
+        .service(rollback_transaction_statement)
+        .service(execute_statement)
+        .service(batch_execute_statement);
+}
@@ -10,6 +10,9 @@ use actix_web::{
 use actix_web::http::{StatusCode};
 use actix_web::error::PayloadError;
 use mysql::error::{Error as MysqlError};
+use mysql::consts::{ColumnType as MysqlColumnType};
+use tokio_postgres::error::{DbError, Error as PostgresError, SqlState};
+use rusqlite::{Error as SqliteError};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BeginTransactionRequest {
     #[serde(rename="resourceArn")]
@@ -82,12 +85,52 @@ pub struct ExecuteStatementRequest {
     pub schema: Option<String>,
     pub database: Option<String>,
     #[serde(rename="continueAfterTimeout")]
-    pub continue_after_timeout: Option<String>,
+    pub continue_after_timeout: Option<bool>,
     #[serde(rename="includeResultMetadata")]
     pub include_result_metadata: Option<bool>,
     pub parameters: Option<Vec<SqlParameter>>,
     #[serde(rename="transactionId")]
     pub transaction_id: Option<String>,
+    #[serde(rename="formatRecordsAs")]
+    pub format_records_as: Option<RecordsFormatType>,
+    #[serde(rename="resultSetOptions")]
+    pub result_set_options: Option<ResultSetOptions>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResultSetOptions {
+    #[serde(rename="decimalReturnType")]
+    pub decimal_return_type: Option<DecimalReturnType>,
+    #[serde(rename="longReturnType")]
+    pub long_return_type: Option<LongReturnType>,
+}
+
+// aws returns DECIMAL as stringValue unless asked otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum DecimalReturnType {
+    #[serde(rename="STRING")]
+    #[default]
+    String,
+    #[serde(rename="DOUBLE_OR_LONG")]
+    DoubleOrLong,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum LongReturnType {
+    #[serde(rename="STRING")]
+    String,
+    #[serde(rename="LONG")]
+    #[default]
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum RecordsFormatType {
+    #[serde(rename="NONE")]
+    #[default]
+    None,
+    #[serde(rename="JSON")]
+    Json,
 }
 
 #[derive(Debug, Serialize)]
@@ -97,9 +140,13 @@ pub struct ExecuteStatementResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename="generatedFields")]
     pub generated_fields: Option<Vec<Field>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub records: Option<Vec<Vec<Field>>>,
     #[serde(rename="columnMetadata")]
     pub column_metadata: Option<Vec<ColumnMetadata>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename="formattedRecords")]
+    pub formatted_records: Option<String>,
 }
 
 
@@ -113,7 +160,7 @@ pub struct BatchExecuteStatementRequest {
     pub schema: Option<String>,
     pub database: Option<String>,
     #[serde(rename="continueAfterTimeout")]
-    pub continue_after_timeout: Option<String>,
+    pub continue_after_timeout: Option<bool>,
     #[serde(rename="includeResultMetadata")]
     pub include_result_metadata: Option<bool>,
     #[serde(rename="parameterSets")]
@@ -174,7 +221,7 @@ pub struct ColumnMetadata {
     pub table_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename="type")]
-    pub type_: Option<u64>,
+    pub type_: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename="typeName")]
     pub type_name: Option<String>,
@@ -183,12 +230,133 @@ pub struct ColumnMetadata {
 pub struct SqlParameter {
     pub name: String,
     pub value: Field,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename="typeHint")]
+    pub type_hint: Option<TypeHint>,
+}
+
+// how a stringValue parameter is passed to the database
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TypeHint {
+    #[serde(rename="DATE")]
+    Date,
+    #[serde(rename="TIME")]
+    Time,
+    #[serde(rename="TIMESTAMP")]
+    Timestamp,
+    #[serde(rename="DECIMAL")]
+    Decimal,
+    #[serde(rename="JSON")]
+    Json,
+    #[serde(rename="UUID")]
+    Uuid,
+}
+
+impl Display for TypeHint {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match *self {
+            TypeHint::Date => "DATE",
+            TypeHint::Time => "TIME",
+            TypeHint::Timestamp => "TIMESTAMP",
+            TypeHint::Decimal => "DECIMAL",
+            TypeHint::Json => "JSON",
+            TypeHint::Uuid => "UUID",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetSecretValueRequest {
+    #[serde(rename="SecretId")]
+    pub secret_id: String,
+    #[serde(rename="VersionId")]
+    pub version_id: Option<String>,
+    #[serde(rename="VersionStage")]
+    pub version_stage: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GetSecretValueResponse {
+    #[serde(rename="ARN")]
+    pub arn: String,
+    #[serde(rename="Name")]
+    pub name: String,
+    #[serde(rename="VersionId")]
+    pub version_id: String,
+    #[serde(rename="SecretString")]
+    pub secret_string: String,
+    #[serde(rename="VersionStages")]
+    pub version_stages: Vec<String>,
+    #[serde(rename="CreatedDate")]
+    pub created_date: f64,
+}
+
+// same shape as the secret RDS creates for a cluster
+#[derive(Debug, Serialize)]
+pub struct DatabaseSecret {
+    pub username: String,
+    pub password: String,
+    pub engine: String,
+    pub host: String,
+    pub port: u16,
+    pub dbname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename="dbClusterIdentifier")]
+    pub db_cluster_identifier: Option<String>,
+}
+
+// the exception names the aws sdk reads from x-amzn-ErrorType / __type
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ErrorType {
+    BadRequestException,
+    StatementTimeoutException,
+    TransactionNotFoundException,
+    InternalServerErrorException,
+    ServiceUnavailableError,
+    DatabaseNotFoundException,
+    DatabaseUnavailableException,
+    DatabaseResumingException,
+    HttpEndpointNotEnabledException,
+    InvalidSecretException,
+    SecretsErrorException,
+    UnsupportedResultException,
+    // secrets manager
+    ResourceNotFoundException,
+    InvalidRequestException,
+}
+
+impl ErrorType {
+    pub fn status(&self) -> u16 {
+        match *self {
+            ErrorType::BadRequestException => 400,
+            ErrorType::StatementTimeoutException => 400,
+            ErrorType::TransactionNotFoundException => 404,
+            ErrorType::InternalServerErrorException => 500,
+            ErrorType::ServiceUnavailableError => 503,
+            ErrorType::DatabaseNotFoundException => 404,
+            ErrorType::DatabaseUnavailableException => 504,
+            ErrorType::DatabaseResumingException => 400,
+            ErrorType::HttpEndpointNotEnabledException => 400,
+            ErrorType::InvalidSecretException => 400,
+            ErrorType::SecretsErrorException => 400,
+            ErrorType::UnsupportedResultException => 400,
+            ErrorType::ResourceNotFoundException => 400,
+            ErrorType::InvalidRequestException => 400,
+        }
+    }
+}
+
+impl Display for ErrorType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{:?}", self)
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct Error {
     pub msg: String,
-    pub status: u16,
+    pub error_type: ErrorType,
 }
 
 impl From<mysql::error::Error> for Error {
@@ -197,84 +365,146 @@ impl From<mysql::error::Error> for Error {
             MysqlError::IoError(_err) => {
                 Error {
                     msg: "Mysql IoErr".to_owned(),
-                    status: 500,
+                    error_type: ErrorType::ServiceUnavailableError,
                 }
             }
             MysqlError::CodecError(_err) => {
                 Error {
                     msg: "Mysql CodecError".to_owned(),
-                    status: 500,
+                    error_type: ErrorType::InternalServerErrorException,
                 }
             }
             MysqlError::MySqlError(err) => {
+                let error_type = match err.code {
+                    // ER_DBACCESS_DENIED_ERROR, ER_ACCESS_DENIED_ERROR: aurora answers a denied secret with a bad request too
+                    1044 | 1045 => ErrorType::BadRequestException,
+                    // ER_QUERY_INTERRUPTED, ER_QUERY_TIMEOUT
+                    1317 | 3024 => ErrorType::StatementTimeoutException,
+                    // ER_BAD_DB_ERROR: USE or connect to a database that does not exist
+                    1049 => ErrorType::DatabaseNotFoundException,
+                    _ => ErrorType::BadRequestException,
+                };
                 Error {
                     msg: err.message.clone(),
-                    status: 400,
+                    error_type: error_type,
                 }
             }
             MysqlError::DriverError(_err) => {
                 Error {
                     msg: "Mysql DriverError".to_owned(),
-                    status: 500,
+                    error_type: ErrorType::InternalServerErrorException,
                 }
             }
             MysqlError::UrlError(_err) => {
                 Error {
                     msg: "Mysql UrlError".to_owned(),
-                    status: 500,
+                    error_type: ErrorType::InternalServerErrorException,
                 }
             }
             MysqlError::TlsError(_err) => {
                 Error {
                     msg: "Mysql TlsError".to_owned(),
-                    status: 500,
+                    error_type: ErrorType::InternalServerErrorException,
                 }
             }
             MysqlError::TlsHandshakeError(_err) => {
                 Error {
                     msg: "Mysql TlsHandshakeError".to_owned(),
-                    status: 500,
+                    error_type: ErrorType::InternalServerErrorException,
                 }
             }
             MysqlError::FromValueError(_err) => {
                 Error {
                     msg: "Mysql FromValueError".to_owned(),
-                    status: 500,
+                    error_type: ErrorType::InternalServerErrorException,
                 }
             }
             MysqlError::FromRowError(_err) => {
                 Error {
                     msg: "Mysql FromRowError".to_owned(),
-                    status: 500,
+                    error_type: ErrorType::InternalServerErrorException,
                 }
             }
         };
         therr
     }
 }
+impl From<PostgresError> for Error {
+    fn from(error: PostgresError) -> Self {
+        // the server error is the source, other errors have none
+        match std::error::Error::source(&error).and_then(|source| source.downcast_ref::<DbError>()) {
+            Some(db_error) => {
+                let error_type = match db_error.code() {
+                    code if *code == SqlState::QUERY_CANCELED => ErrorType::StatementTimeoutException,
+                    code if *code == SqlState::INVALID_CATALOG_NAME => ErrorType::DatabaseNotFoundException,
+                    code if *code == SqlState::INVALID_PASSWORD || *code == SqlState::INVALID_AUTHORIZATION_SPECIFICATION => ErrorType::BadRequestException,
+                    _ => ErrorType::BadRequestException,
+                };
+                Error {
+                    msg: db_error.message().to_owned(),
+                    error_type: error_type,
+                }
+            }
+            None => {
+                Error {
+                    msg: format!("Postgres Error: {}", error),
+                    error_type: ErrorType::InternalServerErrorException,
+                }
+            }
+        }
+    }
+}
+impl From<SqliteError> for Error {
+    fn from(error: SqliteError) -> Self {
+        match error {
+            SqliteError::SqliteFailure(_err, Some(msg)) => {
+                Error {
+                    msg: msg,
+                    error_type: ErrorType::BadRequestException,
+                }
+            }
+            SqliteError::SqliteFailure(err, None) => {
+                Error {
+                    msg: err.to_string(),
+                    error_type: ErrorType::BadRequestException,
+                }
+            }
+            _ => {
+                Error {
+                    msg: format!("Sqlite Error: {}", error),
+                    error_type: ErrorType::InternalServerErrorException,
+                }
+            }
+        }
+    }
+}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{}", to_string_pretty(self).unwrap())
+        match to_string_pretty(self) {
+            Ok(json) => write!(f, "{}", json),
+            Err(_) => write!(f, "{}: {}", self.error_type, self.msg),
+        }
     }
 }
 
 impl ResponseError for Error {
-    // builds the actual response to send back when an error occurs
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.error_type.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+    // builds the actual response to send back when an error occurs, in the aws rest-json error shape
     fn error_response(&self) -> HttpResponse {
-        let err_json = json!({ "error": self.msg });
-        HttpResponse::build(StatusCode::from_u16(self.status).unwrap()).json(err_json)
+        let err_json = json!({ "message": self.msg, "__type": self.error_type });
+        HttpResponse::build(self.status_code())
+            .header("x-amzn-ErrorType", self.error_type.to_string())
+            .json(err_json)
     }
 }
 
 impl From<PayloadError> for Error {
-    fn from(error: PayloadError) -> Self {
-        match error {
-            _ => {
-                Error {
-                    msg: String::from("Payload Error"),
-                    status: 500,
-                }
-            }
+    fn from(_error: PayloadError) -> Self {
+        Error {
+            msg: String::from("Payload Error"),
+            error_type: ErrorType::BadRequestException,
         }
     }
 }
@@ -291,6 +521,8 @@ pub enum ColumnField {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Field {
+    #[serde(rename="arrayValue")]
+    ArrayValue(ArrayValue),
     #[serde(rename="blobValue")]
     BlobValue(String),
     #[serde(rename="booleanValue")]
@@ -303,10 +535,35 @@ pub enum Field {
     LongValue(i64),
     #[serde(rename="stringValue")]
     StringValue(String),
+    #[serde(rename="structValue")]
+    StructValue(StructValue),
+}
+
+// the variants are the wire names of the data api
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ArrayValue {
+    #[serde(rename="arrayValues")]
+    ArrayValues(Vec<ArrayValue>),
+    #[serde(rename="booleanValues")]
+    BooleanValues(Vec<bool>),
+    #[serde(rename="doubleValues")]
+    DoubleValues(Vec<f64>),
+    #[serde(rename="longValues")]
+    LongValues(Vec<i64>),
+    #[serde(rename="stringValues")]
+    StringValues(Vec<String>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructValue {
+    pub attributes: Vec<Field>,
 }
 
 #[derive(Debug, Clone)]
 pub struct MappedMysqlColumnType {
     pub type_name: String,
     pub column_field: ColumnField,
+    pub column_type: MysqlColumnType,
+    pub character_set: u16,
 }
@@ -0,0 +1,65 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::ClusterConfig;
+use crate::model::{Error, ErrorType};
+
+// aurora serverless pauses after autoPauseSeconds without requests,
+// the request that finds it paused and every request in the next resumeSeconds fail while it resumes
+pub struct AutoPause {
+    idle: Option<Duration>,
+    resume: Duration,
+    state: Mutex<PauseState>,
+}
+
+struct PauseState {
+    last_activity: Instant,
+    resumed_at: Option<Instant>,
+}
+
+impl AutoPause {
+    pub fn new(cluster: &ClusterConfig) -> AutoPause {
+        AutoPause {
+            idle: cluster.auto_pause_seconds.map(Duration::from_secs),
+            resume: Duration::from_secs(cluster.resume_seconds()),
+            state: Mutex::new(PauseState {
+                last_activity: Instant::now(),
+                resumed_at: None,
+            }),
+        }
+    }
+    // called on every request that needs the database, fails with DatabaseResumingException while paused or resuming,
+    // a cluster never pauses while a transaction is open on it
+    pub fn wake(&self, resource_arn: &str, has_open_transactions: bool) -> Result<(), Error> {
+        let idle = match self.idle {
+            Some(idle) => idle,
+            None => return Ok(()),
+        };
+        let now = Instant::now();
+        // only instants in there, still good after a panic elsewhere
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match state.resumed_at {
+            Some(resumed_at) if now < resumed_at => return Err(resuming_error(resource_arn)),
+            Some(_) => state.resumed_at = None,
+            None if !has_open_transactions && now.duration_since(state.last_activity) >= idle => {
+                state.resumed_at = Some(now + self.resume);
+                return Err(resuming_error(resource_arn));
+            },
+            None => {},
+        }
+        state.last_activity = now;
+        Ok(())
+    }
+    // the idle time starts over without waking, when a transaction is rolled back by the emulator itself
+    pub fn touch(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.last_activity = Instant::now();
+    }
+}
+
+fn resuming_error(resource_arn: &str) -> Error {
+    Error {
+        msg: format!("The Aurora DB instance {} is resuming after being auto-paused. Please wait a few seconds and try again.", resource_arn),
+        error_type: ErrorType::DatabaseResumingException,
+    }
+}
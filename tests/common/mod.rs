@@ -0,0 +1,96 @@
+// shared by the test files, each of them uses only part of it
+#![allow(dead_code)]
+
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+use std::{env, fs, process, thread};
+
+use serde_json::Value;
+
+pub const RESOURCE_ARN: &str = "arn:aws:rds:us-east-1:123456789012:cluster:dummy";
+pub const SECRET_ARN: &str = "arn:aws:secretsmanager:us-east-1:123456789012:secret:dummy";
+
+// an emulator process of its own for a test, started with the settings the test needs and stopped when it is dropped
+pub struct Emulator {
+    process: Child,
+    endpoint: String,
+    // the database files when ENGINE=sqlite
+    sqlite_path: PathBuf,
+}
+
+impl Emulator {
+    // the settings are environment variables on top of the ones of `cargo test`, ENGINE=mysql reads MYSQL_* from there or .env
+    pub fn start(settings: &[(&str, &str)]) -> Emulator {
+        let port = free_port();
+        let sqlite_path = env::temp_dir().join(format!("data-api-local-test-{}-{}", process::id(), port));
+        fs::create_dir_all(&sqlite_path).unwrap();
+        let process = Command::new(env!("CARGO_BIN_EXE_data-api-local"))
+            .env_remove("CLUSTERS_CONFIG")
+            .env("RESOURCE_ARN", RESOURCE_ARN)
+            .env("SECRET_ARN", SECRET_ARN)
+            .env("HOST", "127.0.0.1")
+            .env("PORT", port.to_string())
+            .env("JSONLIMIT", "4194304")
+            .env("SQLITE_PATH", &sqlite_path)
+            .envs(settings.iter().cloned())
+            .spawn()
+            .unwrap();
+        let mut emulator = Emulator {
+            process,
+            endpoint: format!("http://127.0.0.1:{}", port),
+            sqlite_path,
+        };
+        emulator.wait_until_listening(port);
+        emulator
+    }
+
+    fn wait_until_listening(&mut self, port: u16) {
+        let started_at = Instant::now();
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            if let Some(status) = self.process.try_wait().unwrap() {
+                panic!("the emulator exited on start with {}", status);
+            }
+            assert!(started_at.elapsed() < Duration::from_secs(10), "the emulator did not start listening on {}", port);
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.endpoint, path)
+    }
+
+    pub async fn post(&self, path: &str, body: Value) -> (u16, Value) {
+        let response = reqwest::Client::new().post(&self.url(path))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        (response.status().as_u16(), response.json().await.unwrap())
+    }
+
+    // the body as it came, for comparing the exact json
+    pub async fn post_text(&self, path: &str, body: Value) -> String {
+        reqwest::Client::new().post(&self.url(path))
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    }
+}
+
+impl Drop for Emulator {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = fs::remove_dir_all(&self.sqlite_path);
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
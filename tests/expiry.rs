@@ -0,0 +1,57 @@
+extern crate reqwest;
+use std::time::Duration;
+use serde_json::{json, Value};
+
+mod common;
+
+use common::{Emulator, RESOURCE_ARN, SECRET_ARN};
+
+// any engine expires transactions the same way, sqlite needs no database server
+fn start_emulator() -> Emulator {
+    Emulator::start(&[("ENGINE", "sqlite"), ("TRANSACTION_IDLE_TIMEOUT", "2"), ("TRANSACTION_MAX_LIFETIME", "6")])
+}
+
+async fn begin_transaction(emulator: &Emulator) -> String {
+    let (status, body) = emulator.post("/BeginTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN})).await;
+    assert_eq!(status, 200);
+    body["transactionId"].as_str().unwrap().to_owned()
+}
+
+async fn execute(emulator: &Emulator, transaction_id: &str) -> (u16, Value) {
+    emulator.post("/Execute", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id, "sql": "SELECT 1"})).await
+}
+
+fn assert_not_found(transaction_id: &str, (status, body): (u16, Value)) {
+    assert_eq!(status, 404);
+    assert_eq!(body, json!({"__type": "TransactionNotFoundException", "message": format!("Transaction {} is not found", transaction_id)}));
+}
+
+#[actix_rt::test]
+async fn idle_transaction_is_rolled_back() {
+    let emulator = start_emulator();
+    let transaction_id = begin_transaction(&emulator).await;
+    actix_rt::time::delay_for(Duration::from_secs(1)).await;
+    assert_eq!(execute(&emulator, &transaction_id).await.0, 200);
+
+    // the reaper looks every second, so it is gone one second after the idle timeout at the latest
+    actix_rt::time::delay_for(Duration::from_millis(3500)).await;
+    assert_not_found(&transaction_id, execute(&emulator, &transaction_id).await);
+    assert_not_found(&transaction_id, emulator.post("/CommitTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id})).await);
+}
+
+#[actix_rt::test]
+async fn transaction_is_rolled_back_after_its_lifetime() {
+    let emulator = start_emulator();
+    let transaction_id = begin_transaction(&emulator).await;
+    // never idle for 2 seconds, still gone after 6 seconds in total
+    for _ in 0..5 {
+        actix_rt::time::delay_for(Duration::from_secs(1)).await;
+        assert_eq!(execute(&emulator, &transaction_id).await.0, 200);
+    }
+    // the reaper may not have rolled it back at 6.5 seconds yet, at 7.5 it has
+    actix_rt::time::delay_for(Duration::from_millis(1500)).await;
+    execute(&emulator, &transaction_id).await;
+    actix_rt::time::delay_for(Duration::from_secs(1)).await;
+    assert_not_found(&transaction_id, execute(&emulator, &transaction_id).await);
+    assert_not_found(&transaction_id, emulator.post("/RollbackTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id})).await);
+}
@@ -0,0 +1,134 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::{test, App};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use data_api_local::backend::{Backend, Connection, ExecuteOptions};
+use data_api_local::config::{ClusterConfig, SecretConfig};
+use data_api_local::model::{Error, ExecuteStatementResponse, Field, SqlParameter, UpdateResult};
+use data_api_local::{configure, AppData, Cluster};
+
+const RESOURCE_ARN: &str = "arn:aws:rds:us-east-1:123456789012:cluster:stub";
+const SECRET_ARN: &str = "arn:aws:secretsmanager:us-east-1:123456789012:secret:stub";
+
+// the handlers run in process against a backend that writes down what it is asked to do
+struct StubBackend {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+struct StubConnection {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubConnection {
+    fn call(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+#[async_trait(?Send)]
+impl Backend for StubBackend {
+    async fn connect(&self, secret: &SecretConfig, database: Option<String>, _schema: Option<String>) -> Result<Box<dyn Connection>, Error> {
+        self.calls.lock().unwrap().push(format!("connect {} {}", secret.user.clone().unwrap_or_default(), database.as_deref().unwrap_or("default")));
+        Ok(Box::new(StubConnection {
+            calls: self.calls.clone(),
+        }))
+    }
+}
+
+#[async_trait(?Send)]
+impl Connection for StubConnection {
+    async fn begin(&mut self) -> Result<(), Error> {
+        self.call("begin".to_owned());
+        Ok(())
+    }
+    async fn select_database_and_schema(&mut self, _database: Option<String>, _schema: Option<String>) -> Result<(), Error> {
+        Ok(())
+    }
+    async fn execute(&mut self, sql: &str, parameters: Vec<SqlParameter>, _options: &ExecuteOptions) -> Result<ExecuteStatementResponse, Error> {
+        self.call(format!("execute {}", sql));
+        Ok(ExecuteStatementResponse {
+            number_of_records_updated: 0,
+            generated_fields: None,
+            records: Some(vec![parameters.into_iter().map(|parameter| parameter.value).collect()]),
+            column_metadata: Some(Vec::new()),
+            formatted_records: None,
+        })
+    }
+    async fn batch_execute(&mut self, sql: &str, parameter_sets: Vec<Vec<SqlParameter>>) -> Result<Vec<UpdateResult>, Error> {
+        self.call(format!("batch_execute {}", sql));
+        Ok(parameter_sets.iter().map(|_| UpdateResult {
+            generated_fields: vec![Field::LongValue(1)],
+        }).collect())
+    }
+    async fn commit(&mut self) -> Result<(), Error> {
+        self.call("commit".to_owned());
+        Ok(())
+    }
+    async fn rollback(&mut self) -> Result<(), Error> {
+        self.call("rollback".to_owned());
+        Ok(())
+    }
+}
+
+fn stub_app_data(calls: Arc<Mutex<Vec<String>>>) -> AppData {
+    let config: ClusterConfig = serde_json::from_value(json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArns": [SECRET_ARN],
+        "engine": "stub",
+        "user": "stub_user",
+    })).unwrap();
+    AppData::new(vec![Cluster::with_backend(Arc::new(StubBackend { calls }), config)], Duration::from_secs(45), 1024 * 1024)
+}
+
+async fn post(app_data: &AppData, path: &str, body: Value) -> (u16, Value) {
+    let mut app = test::init_service(App::new().data(app_data.clone()).configure(configure)).await;
+    let request = test::TestRequest::post().uri(path).set_json(&body).to_request();
+    let response = test::call_service(&mut app, request).await;
+    let status = response.status().as_u16();
+    (status, serde_json::from_slice(&test::read_body(response).await).unwrap())
+}
+
+#[actix_rt::test]
+async fn execute_runs_on_a_connection_of_the_secret() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let app_data = stub_app_data(calls.clone());
+    let (status, body) = post(&app_data, "/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": "stub_db",
+        "sql": "SELECT :id",
+        "parameters": [{"name": "id", "value": {"longValue": 7}}],
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({"numberOfRecordsUpdated": 0, "records": [[{"longValue": 7}]], "columnMetadata": []}));
+    assert_eq!(*calls.lock().unwrap(), ["connect stub_user stub_db", "execute SELECT :id"]);
+}
+
+#[actix_rt::test]
+async fn transaction_keeps_its_connection_until_commit() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let app_data = stub_app_data(calls.clone());
+    let (status, body) = post(&app_data, "/BeginTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN})).await;
+    assert_eq!(status, 200);
+    let transaction_id = body["transactionId"].as_str().unwrap().to_owned();
+    let (status, body) = post(&app_data, "/BatchExecute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "sql": "INSERT INTO doc VALUES (:id)",
+        "parameterSets": [[{"name": "id", "value": {"longValue": 1}}], [{"name": "id", "value": {"longValue": 2}}]],
+        "transactionId": transaction_id,
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({"updateResults": [{"generatedFields": [{"longValue": 1}]}, {"generatedFields": [{"longValue": 1}]}]}));
+    let (status, body) = post(&app_data, "/CommitTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id})).await;
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({"transactionStatus": "Transaction Committed"}));
+    assert_eq!(*calls.lock().unwrap(), ["connect stub_user default", "begin", "batch_execute INSERT INTO doc VALUES (:id)", "commit"]);
+
+    let (status, body) = post(&app_data, "/RollbackTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id})).await;
+    assert_eq!(status, 404);
+    assert_eq!(body["__type"], "TransactionNotFoundException");
+}
@@ -18,7 +18,7 @@ pub struct ExecuteStatementRequest {
     pub schema: Option<String>,
     pub database: Option<String>,
     #[serde(rename="continueAfterTimeout")]
-    pub continue_after_timeout: Option<String>,
+    pub continue_after_timeout: Option<bool>,
     #[serde(rename="includeResultMetadata")]
     pub include_result_metadata: Option<bool>,
     pub parameters: Option<Vec<SqlParameter>>,
@@ -81,11 +81,14 @@ pub enum Field {
 //     assert_eq!(body, "{\"stringValue\":\"ok\"}");
 // }
 
-const RESOURCE_ARN: &'static str = "arn:aws:rds:us-east-1:123456789012:cluster:dummy";
-const SECRET_ARN: &'static str = "arn:aws:secretsmanager:us-east-1:123456789012:secret:dummy";
-const DATABASE_TEST: &'static str = "leliam_data_api";
-const DATABASE_MAIN: &'static str = "mysql";
-const CREATE_TEST_TABLE_SCHEMA: &'static str = "CREATE TABLE doc(
+const RESOURCE_ARN: &str = "arn:aws:rds:us-east-1:123456789012:cluster:dummy";
+const SECRET_ARN: &str = "arn:aws:secretsmanager:us-east-1:123456789012:secret:dummy";
+// from tests/integration.json, the readonly user is created by the tests that need it
+const READONLY_SECRET_ARN: &str = "arn:aws:secretsmanager:us-east-1:123456789012:secret:readonly";
+const WRONG_PASSWORD_SECRET_ARN: &str = "arn:aws:secretsmanager:us-east-1:123456789012:secret:wrong-password";
+const DATABASE_TEST: &str = "leliam_data_api";
+const DATABASE_MAIN: &str = "mysql";
+const CREATE_TEST_TABLE_SCHEMA: &str = "CREATE TABLE doc(
     `key` varchar(255) NOT NULL,
     `content` longtext NULL,
     `content_extra` longtext NULL,
@@ -93,7 +96,7 @@ const CREATE_TEST_TABLE_SCHEMA: &'static str = "CREATE TABLE doc(
     `updated_at` timestamp DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP NOT NULL,
     PRIMARY KEY (`key`)
 );";
-const CREATE_TEST_TABLEB_SCHEMA: &'static str = "CREATE TABLE docb (
+const CREATE_TEST_TABLEB_SCHEMA: &str = "CREATE TABLE docb (
     `key` varchar(255) NOT NULL,
     `content` longtext NULL,
     `content_extra` longtext NULL,
@@ -323,3 +326,909 @@ async fn step_2_perform_transaction() {
         .unwrap();
     assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"stringValue\":\"2021-02-08 13:18:58\"}]],\"columnMetadata\":[]}");
 }
+
+#[actix_rt::test]
+async fn step_3_column_metadata() {
+    let client = reqwest::Client::new();
+    // the results are utf8mb4, a LONGTEXT of 4294967295 bytes holds a quarter as many characters
+    let body: serde_json::Value = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT `key`, content, created_at FROM doc WHERE 1 = 0",
+            "includeResultMetadata": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["columnMetadata"], serde_json::json!([
+        {"arrayBaseColumnType": 0, "isAutoIncrement": false, "isCaseSensitive": false, "isCurrency": false, "isSigned": false, "label": "key", "name": "key", "nullable": 0, "precision": 255, "scale": 0, "schemaName": DATABASE_TEST, "tableName": "doc", "type": 12, "typeName": "VARCHAR"},
+        {"arrayBaseColumnType": 0, "isAutoIncrement": false, "isCaseSensitive": false, "isCurrency": false, "isSigned": false, "label": "content", "name": "content", "nullable": 1, "precision": 1073741823, "scale": 0, "schemaName": DATABASE_TEST, "tableName": "doc", "type": -1, "typeName": "LONGTEXT"},
+        {"arrayBaseColumnType": 0, "isAutoIncrement": false, "isCaseSensitive": false, "isCurrency": false, "isSigned": false, "label": "created_at", "name": "created_at", "nullable": 0, "precision": 19, "scale": 0, "schemaName": DATABASE_TEST, "tableName": "doc", "type": 93, "typeName": "TIMESTAMP"},
+    ]));
+}
+
+#[actix_rt::test]
+async fn step_4_temporal_values() {
+    let client = reqwest::Client::new();
+    let statements = [
+        "DROP TABLE IF EXISTS temporal",
+        "CREATE TABLE temporal (
+            `id` int NOT NULL,
+            `d` date NULL,
+            `dt` datetime(3) NULL,
+            `ts` timestamp(6) NULL,
+            `t` time(1) NULL,
+            `y` year NULL,
+            PRIMARY KEY (`id`)
+        )",
+        "INSERT INTO temporal VALUES
+            (1, '2021-02-08', '2021-02-08 13:18:58.120', '2021-02-08 13:18:58.123456', '13:18:58.5', 2021),
+            (2, '2021-02-08', '2021-02-08 13:18:58.000', '2021-02-08 13:18:58', '-01:02:03', 1999),
+            (3, '0000-00-00', '0000-00-00 00:00:00', '0000-00-00 00:00:00', '838:59:59', NULL),
+            (4, NULL, NULL, NULL, '-838:59:59.0', NULL),
+            (5, NULL, NULL, NULL, '100:00:00.1', NULL)",
+    ];
+    for sql in statements.iter() {
+        let response = client.post("http://localhost:8080/Execute")
+            .json(&serde_json::json!({
+                "resourceArn": RESOURCE_ARN,
+                "secretArn": SECRET_ARN,
+                "database": DATABASE_TEST,
+                "sql": sql,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    // golden values, one row per case: fraction, no fraction, zero dates, negative and over 24 hours TIME
+    let golden = serde_json::json!([
+        [{"stringValue": "2021-02-08"}, {"stringValue": "2021-02-08 13:18:58.12"}, {"stringValue": "2021-02-08 13:18:58.123456"}, {"stringValue": "13:18:58.5"}, {"stringValue": "2021"}],
+        [{"stringValue": "2021-02-08"}, {"stringValue": "2021-02-08 13:18:58"}, {"stringValue": "2021-02-08 13:18:58"}, {"stringValue": "-01:02:03"}, {"stringValue": "1999"}],
+        [{"stringValue": "0000-00-00"}, {"stringValue": "0000-00-00 00:00:00"}, {"stringValue": "0000-00-00 00:00:00"}, {"stringValue": "838:59:59"}, {"isNull": true}],
+        [{"isNull": true}, {"isNull": true}, {"isNull": true}, {"stringValue": "-838:59:59"}, {"isNull": true}],
+        [{"isNull": true}, {"isNull": true}, {"isNull": true}, {"stringValue": "100:00:00.1"}, {"isNull": true}],
+    ]);
+    // without parameters mysql sends text, with parameters the binary protocol, both have to render the same
+    let requests = [
+        serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT d, dt, ts, t, y FROM temporal ORDER BY id",
+        }),
+        serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT d, dt, ts, t, y FROM temporal WHERE id >= :min_id ORDER BY id",
+            "parameters": [
+                {"name": "min_id", "value": {"longValue": 1}},
+            ],
+        }),
+    ];
+    for request in requests.iter() {
+        let body: serde_json::Value = client.post("http://localhost:8080/Execute")
+            .json(request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["records"], golden);
+    }
+
+    let body: serde_json::Value = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT y FROM temporal WHERE id = 1",
+            "includeResultMetadata": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["columnMetadata"][0]["type"], 5);
+    assert_eq!(body["columnMetadata"][0]["typeName"], "YEAR");
+}
+
+#[actix_rt::test]
+async fn step_5_tinyint_and_bit_values() {
+    let client = reqwest::Client::new();
+    let statements = [
+        "DROP TABLE IF EXISTS flags",
+        "CREATE TABLE flags (
+            `id` int NOT NULL,
+            `is_active` tinyint(1) NULL,
+            `status` tinyint NULL,
+            `level` tinyint unsigned NULL,
+            `is_visible` bit(1) NULL,
+            `mask` bit(10) NULL,
+            PRIMARY KEY (`id`)
+        )",
+        "INSERT INTO flags VALUES (1, 5, 5, 255, b'1', b'1000000001'), (2, 0, -128, 0, b'0', b'0')",
+    ];
+    for sql in statements.iter() {
+        let response = client.post("http://localhost:8080/Execute")
+            .json(&serde_json::json!({
+                "resourceArn": RESOURCE_ARN,
+                "secretArn": SECRET_ARN,
+                "database": DATABASE_TEST,
+                "sql": sql,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    let expected = serde_json::json!([
+        [{"booleanValue": true}, {"longValue": 5}, {"longValue": 255}, {"booleanValue": true}, {"blobValue": "AgE="}],
+        [{"booleanValue": false}, {"longValue": -128}, {"longValue": 0}, {"booleanValue": false}, {"blobValue": "AAA="}],
+    ]);
+    let requests = [
+        serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT is_active, status, level, is_visible, mask FROM flags ORDER BY id",
+        }),
+        serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT is_active, status, level, is_visible, mask FROM flags WHERE id >= :min_id ORDER BY id",
+            "parameters": [
+                {"name": "min_id", "value": {"longValue": 1}},
+            ],
+        }),
+    ];
+    for request in requests.iter() {
+        let body: serde_json::Value = client.post("http://localhost:8080/Execute")
+            .json(request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["records"], expected);
+    }
+
+    let body: serde_json::Value = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT is_active, status, level, is_visible, mask FROM flags WHERE 1 = 0",
+            "includeResultMetadata": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let types: Vec<(serde_json::Value, serde_json::Value)> = body["columnMetadata"].as_array().unwrap().iter()
+        .map(|column| (column["type"].clone(), column["typeName"].clone()))
+        .collect();
+    assert_eq!(types, vec![
+        (serde_json::json!(-7), serde_json::json!("BIT")),
+        (serde_json::json!(-6), serde_json::json!("TINYINT")),
+        (serde_json::json!(-6), serde_json::json!("TINYINT UNSIGNED")),
+        (serde_json::json!(-7), serde_json::json!("BIT")),
+        (serde_json::json!(-7), serde_json::json!("BIT")),
+    ]);
+}
+
+#[actix_rt::test]
+async fn step_6_json_enum_set_and_geometry_values() {
+    let client = reqwest::Client::new();
+    let statements = [
+        "DROP TABLE IF EXISTS places",
+        "CREATE TABLE places (
+            `id` int NOT NULL,
+            `kind` enum('shop', 'home') NULL,
+            `tags` set('a', 'b', 'c') NULL,
+            `location` geometry NULL,
+            `attributes` json NULL,
+            PRIMARY KEY (`id`)
+        )",
+        "INSERT INTO places VALUES (1, 'shop', 'a,c', ST_GeomFromText('POINT(1 2)'), '{\"b\": 1, \"a\": [true, null]}')",
+    ];
+    for sql in statements.iter() {
+        let response = client.post("http://localhost:8080/Execute")
+            .json(&serde_json::json!({
+                "resourceArn": RESOURCE_ARN,
+                "secretArn": SECRET_ARN,
+                "database": DATABASE_TEST,
+                "sql": sql,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    let body: serde_json::Value = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT kind, tags, location, attributes, ST_AsText(location) AS location_wkt FROM places WHERE id = :id",
+            "parameters": [
+                {"name": "id", "value": {"longValue": 1}},
+            ],
+            "includeResultMetadata": true,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["records"], serde_json::json!([[
+        {"stringValue": "shop"}, {"stringValue": "a,c"}, {"blobValue": "AAAAAAEBAAAAAAAAAAAA8D8AAAAAAAAAQA=="}, {"stringValue": "{\"a\": [true, null], \"b\": 1}"}, {"stringValue": "POINT(1 2)"},
+    ]]));
+    let type_names: Vec<serde_json::Value> = body["columnMetadata"].as_array().unwrap().iter()
+        .take(4)
+        .map(|column| column["typeName"].clone())
+        .collect();
+    assert_eq!(type_names, vec!["ENUM", "SET", "GEOMETRY", "JSON"]);
+}
+
+#[actix_rt::test]
+async fn step_7_strings_are_decoded_with_their_charset() {
+    let client = reqwest::Client::new();
+    let statements = [
+        "DROP TABLE IF EXISTS products",
+        "CREATE TABLE products (
+            `id` int NOT NULL,
+            `name_latin1` varchar(255) CHARACTER SET latin1 NULL,
+            `name_utf8mb4` varchar(255) CHARACTER SET utf8mb4 NULL,
+            PRIMARY KEY (`id`)
+        )",
+        "INSERT INTO products VALUES (1, 'Café crème', 'Café 日本語 😀')",
+    ];
+    for sql in statements.iter() {
+        let response = client.post("http://localhost:8080/Execute")
+            .json(&serde_json::json!({
+                "resourceArn": RESOURCE_ARN,
+                "secretArn": SECRET_ARN,
+                "database": DATABASE_TEST,
+                "sql": sql,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    let expected = serde_json::json!([[{"stringValue": "Café crème"}, {"stringValue": "Café 日本語 😀"}]]);
+    let body: serde_json::Value = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+            "sql": "SELECT name_latin1, name_utf8mb4 FROM products",
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["records"], expected);
+
+    // without character_set_results the latin1 column is sent as latin1 bytes
+    let transaction: BeginTransactionResponse = client.post("http://localhost:8080/BeginTransaction")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_TEST,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let mut bodies: Vec<serde_json::Value> = Vec::new();
+    for sql in ["SET character_set_results = NULL", "SELECT name_latin1, name_utf8mb4 FROM products", "SELECT _utf8mb4 X'C328' AS broken"].iter() {
+        bodies.push(client.post("http://localhost:8080/Execute")
+            .json(&serde_json::json!({
+                "resourceArn": RESOURCE_ARN,
+                "secretArn": SECRET_ARN,
+                "database": DATABASE_TEST,
+                "sql": sql,
+                "transactionId": transaction.transaction_id,
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap());
+    }
+    assert_eq!(bodies[1]["records"], expected);
+    assert_eq!(bodies[2], serde_json::json!({"__type": "UnsupportedResultException", "message": "Column broken is not valid UTF-8 text"}));
+
+    let response = client.post("http://localhost:8080/RollbackTransaction")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "transactionId": transaction.transaction_id,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[actix_rt::test]
+async fn unknown_resource_arn_is_rejected() {
+    let req = BeginTransactionRequest {
+        resource_arn: "arn:aws:rds:us-east-1:123456789012:cluster:unknown".to_owned(),
+        secret_arn: SECRET_ARN.to_owned(),
+        schema: None,
+        database: Some(DATABASE_TEST.to_owned()),
+    };
+
+    let client = reqwest::Client::new();
+    let response = client.post("http://localhost:8080/BeginTransaction")
+        .json(&req)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.headers()["x-amzn-ErrorType"], "HttpEndpointNotEnabledException");
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"HttpEndpointNotEnabledException\",\"message\":\"HttpEndPoint is not enabled for arn:aws:rds:us-east-1:123456789012:cluster:unknown\"}");
+}
+
+#[actix_rt::test]
+async fn unknown_secret_arn_is_rejected() {
+    let req = BeginTransactionRequest {
+        resource_arn: RESOURCE_ARN.to_owned(),
+        secret_arn: "arn:aws:secretsmanager:us-east-1:123456789012:secret:unknown".to_owned(),
+        schema: None,
+        database: Some(DATABASE_TEST.to_owned()),
+    };
+
+    let client = reqwest::Client::new();
+    let response = client.post("http://localhost:8080/BeginTransaction")
+        .json(&req)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.headers()["x-amzn-ErrorType"], "SecretsErrorException");
+}
+
+#[actix_rt::test]
+async fn missing_database_is_not_found() {
+    let req = BeginTransactionRequest {
+        resource_arn: RESOURCE_ARN.to_owned(),
+        secret_arn: SECRET_ARN.to_owned(),
+        schema: None,
+        database: Some("leliam_data_api_missing".to_owned()),
+    };
+
+    let client = reqwest::Client::new();
+    let response = client.post("http://localhost:8080/BeginTransaction")
+        .json(&req)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 404);
+    assert_eq!(response.headers()["x-amzn-ErrorType"], "DatabaseNotFoundException");
+}
+
+#[actix_rt::test]
+async fn unconvertible_value_is_unsupported_result() {
+    let client = reqwest::Client::new();
+    let response = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT _utf8mb4 X'C328' AS broken",
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.headers().get("x-amzn-ErrorType").unwrap(), "UnsupportedResultException");
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"UnsupportedResultException\",\"message\":\"Column broken is not valid UTF-8 text\"}");
+
+    // the emulator is still serving after the failed row
+    let body = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT 1 AS one",
+        }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"longValue\":1}]],\"columnMetadata\":[]}");
+}
+
+#[actix_rt::test]
+async fn database_name_is_quoted_and_validated() {
+    let client = reqwest::Client::new();
+    let response = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": "mysql; DROP DATABASE leliam_data_api",
+            "sql": "SELECT 1",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"DatabaseNotFoundException\",\"message\":\"Unknown database 'mysql; DROP DATABASE leliam_data_api'\"}");
+
+    let response = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": "a".repeat(65),
+            "sql": "SELECT 1",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.text().await.unwrap(), format!("{{\"__type\":\"BadRequestException\",\"message\":\"1 validation error detected: Value '{}' at 'database' failed to satisfy constraint: Member must have length less than or equal to 64\"}}", "a".repeat(65)));
+
+    let response = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "schema": "public",
+            "sql": "SELECT 1",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"BadRequestException\",\"message\":\"The schema parameter isn't supported for Aurora MySQL, use database instead\"}");
+}
+
+#[actix_rt::test]
+async fn unknown_transaction_is_not_found() {
+    let req = CommitTransactionRequest {
+        resource_arn: RESOURCE_ARN.to_owned(),
+        secret_arn: SECRET_ARN.to_owned(),
+        transaction_id: "unknown".to_owned(),
+    };
+
+    let client = reqwest::Client::new();
+    let response = client.post("http://localhost:8080/CommitTransaction")
+        .json(&req)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 404);
+    assert_eq!(response.headers()["x-amzn-ErrorType"], "TransactionNotFoundException");
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"TransactionNotFoundException\",\"message\":\"Transaction unknown is not found\"}");
+}
+
+#[actix_rt::test]
+async fn get_secret_value_returns_connection_secret() {
+    let client = reqwest::Client::new();
+    let body = client.post("http://localhost:8080/")
+        .header("X-Amz-Target", "secretsmanager.GetSecretValue")
+        .header("Content-Type", "application/x-amz-json-1.1")
+        .body(format!("{{\"SecretId\":\"{}\"}}", SECRET_ARN))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    let response: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(response["ARN"], SECRET_ARN);
+    assert_eq!(response["Name"], "dummy");
+    let secret: serde_json::Value = serde_json::from_str(response["SecretString"].as_str().unwrap()).unwrap();
+    assert_eq!(secret["username"], "root");
+    assert_eq!(secret["password"], "example");
+    assert_eq!(secret["engine"], "mysql");
+    assert_eq!(secret["port"], 3306);
+}
+
+#[actix_rt::test]
+async fn response_over_size_limit_is_rejected() {
+    let req: ExecuteStatementRequest = ExecuteStatementRequest {
+        resource_arn: RESOURCE_ARN.to_owned(),
+        secret_arn: SECRET_ARN.to_owned(),
+        sql: "SELECT REPEAT('a', 1048576)".to_owned(),
+        schema: None,
+        database: Some(DATABASE_MAIN.to_owned()),
+        continue_after_timeout: None,
+        include_result_metadata: None,
+        parameters: None,
+        transaction_id: None,
+    };
+
+    let client = reqwest::Client::new();
+    let response = client.post("http://localhost:8080/Execute")
+        .json(&req)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"BadRequestException\",\"message\":\"Database returned more than the allowed response size limit\"}");
+}
+
+#[actix_rt::test]
+async fn format_records_as_json() {
+    let client = reqwest::Client::new();
+    let body = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT 1 AS id, 'doc_a' AS `key`, NULL AS price",
+            "formatRecordsAs": "JSON",
+        }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"columnMetadata\":[],\"formattedRecords\":\"[{\\\"id\\\":1,\\\"key\\\":\\\"doc_a\\\",\\\"price\\\":null}]\"}");
+}
+
+#[actix_rt::test]
+async fn result_set_options_render_decimal_and_long() {
+    let client = reqwest::Client::new();
+    let sql = "SELECT CAST(12.50 AS DECIMAL(10, 2)) AS price, CAST(12 AS DECIMAL(10, 0)) AS quantity, CAST(9007199254740993 AS SIGNED) AS id";
+    let body = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": sql,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"stringValue\":\"12.50\"},{\"stringValue\":\"12\"},{\"longValue\":9007199254740993}]],\"columnMetadata\":[]}");
+
+    let body = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": sql,
+            "resultSetOptions": {"decimalReturnType": "DOUBLE_OR_LONG", "longReturnType": "STRING"},
+        }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"doubleValue\":12.5},{\"longValue\":12},{\"stringValue\":\"9007199254740993\"}]],\"columnMetadata\":[]}");
+}
+
+#[actix_rt::test]
+async fn large_numbers_keep_their_digits() {
+    let client = reqwest::Client::new();
+    let columns = "CAST(18446744073709551615 AS UNSIGNED) AS max_id, CAST(9223372036854775807 AS UNSIGNED) AS id, CAST('1234567890.123456789' AS DECIMAL(20, 9)) AS price, CAST('123456789012345678901234567890' AS DECIMAL(30, 0)) AS quantity";
+    let expected = serde_json::json!([[
+        {"stringValue": "18446744073709551615"}, {"longValue": 9223372036854775807i64}, {"doubleValue": 1234567890.1234568}, {"stringValue": "123456789012345678901234567890"},
+    ]]);
+    // text protocol without parameters, binary protocol with them
+    let requests = [
+        serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": format!("SELECT {}", columns),
+            "resultSetOptions": {"decimalReturnType": "DOUBLE_OR_LONG"},
+        }),
+        serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": format!("SELECT {} FROM DUAL WHERE :one = 1", columns),
+            "parameters": [
+                {"name": "one", "value": {"longValue": 1}},
+            ],
+            "resultSetOptions": {"decimalReturnType": "DOUBLE_OR_LONG"},
+        }),
+    ];
+    for request in requests.iter() {
+        let body: serde_json::Value = client.post("http://localhost:8080/Execute")
+            .json(request)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["records"], expected);
+    }
+
+    let body = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT CAST('1234567890.123456789' AS DECIMAL(20, 9)) AS price",
+        }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"stringValue\":\"1234567890.123456789\"}]],\"columnMetadata\":[]}");
+}
+
+#[actix_rt::test]
+async fn type_hint_binds_and_validates_values() {
+    let client = reqwest::Client::new();
+    let body = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT DATE_ADD(:day, INTERVAL 1 DAY) AS next_day",
+            "parameters": [
+                {"name": "day", "value": {"stringValue": "2021-02-28"}, "typeHint": "DATE"},
+            ],
+        }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"stringValue\":\"2021-03-01\"}]],\"columnMetadata\":[]}");
+
+    let response = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT :day AS day",
+            "parameters": [
+                {"name": "day", "value": {"stringValue": "28/02/2021"}, "typeHint": "DATE"},
+            ],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"BadRequestException\",\"message\":\"Invalid value for parameter day with typeHint DATE: '28/02/2021' is not YYYY-MM-DD\"}");
+}
+
+#[actix_rt::test]
+async fn array_parameters_are_rejected() {
+    let client = reqwest::Client::new();
+    let response = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT :ids AS ids",
+            "parameters": [
+                {"name": "ids", "value": {"arrayValue": {"arrayValues": [{"longValues": [1, 2]}, {"longValues": [3]}]}}},
+            ],
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"BadRequestException\",\"message\":\"Array parameters are not supported.\"}");
+}
+
+#[actix_rt::test]
+async fn blob_value_round_trip() {
+    let client = reqwest::Client::new();
+    let body = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT CAST(:thumbnail AS BINARY) AS thumbnail, HEX(:thumbnail) AS thumbnail_hex",
+            "parameters": [
+                {"name": "thumbnail", "value": {"blobValue": "AAEC/w=="}},
+            ],
+        }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"blobValue\":\"AAEC/w==\"},{\"stringValue\":\"000102FF\"}]],\"columnMetadata\":[]}");
+
+    let response = client.post("http://localhost:8080/Execute")
+        .json(&serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT :thumbnail AS thumbnail",
+            "parameters": [
+                {"name": "thumbnail", "value": {"blobValue": "not base64!"}},
+            ],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.text().await.unwrap(), "{\"__type\":\"BadRequestException\",\"message\":\"Invalid blobValue for parameter thumbnail: not a valid base64 value\"}");
+}
+
+#[actix_rt::test]
+async fn text_with_a_bin_collation_is_a_string() {
+    let (status, _, body) = post_json("/BeginTransaction", serde_json::json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_MAIN,
+    })).await;
+    assert_eq!(status, 200);
+    let transaction_id = body["transactionId"].as_str().unwrap().to_owned();
+    for sql in [
+        "CREATE TEMPORARY TABLE note (content TEXT COLLATE utf8mb4_bin, thumbnail BLOB)",
+        "INSERT INTO note VALUES ('Abc', 'Abc')",
+    ] {
+        let (status, _, body) = post_json("/Execute", serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "sql": sql,
+            "transactionId": transaction_id,
+        })).await;
+        assert_eq!(status, 200, "{}", body);
+    }
+    let (status, _, body) = post_json("/Execute", serde_json::json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "sql": "SELECT content, thumbnail FROM note",
+        "transactionId": transaction_id,
+        "includeResultMetadata": true,
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["records"], serde_json::json!([[{"stringValue": "Abc"}, {"blobValue": "QWJj"}]]));
+    assert_eq!(body["columnMetadata"][0]["typeName"], "TEXT");
+    assert_eq!(body["columnMetadata"][1]["typeName"], "BLOB");
+    let (status, _, _) = post_json("/RollbackTransaction", serde_json::json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "transactionId": transaction_id,
+    })).await;
+    assert_eq!(status, 200);
+}
+
+async fn post_json(path: &str, body: serde_json::Value) -> (u16, String, serde_json::Value) {
+    let client = reqwest::Client::new();
+    let response = client.post(&format!("http://localhost:8080{}", path))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+    let error_type = response.headers().get("x-amzn-ErrorType").map(|value| value.to_str().unwrap().to_owned()).unwrap_or_default();
+    (status, error_type, response.json().await.unwrap())
+}
+
+// a user that may only read mysql.help_topic, enough to connect to the mysql database
+async fn create_readonly_user() {
+    for sql in [
+        "CREATE USER IF NOT EXISTS 'data_api_readonly'@'%' IDENTIFIED BY 'readonly'",
+        "GRANT SELECT ON mysql.help_topic TO 'data_api_readonly'@'%'",
+    ] {
+        let (status, _, body) = post_json("/Execute", serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": DATABASE_MAIN,
+            "sql": sql,
+        })).await;
+        assert_eq!(status, 200, "{}", body);
+    }
+}
+
+#[actix_rt::test]
+async fn secrets_connect_as_their_own_user() {
+    create_readonly_user().await;
+    for (secret_arn, user) in [(SECRET_ARN, "root"), (READONLY_SECRET_ARN, "data_api_readonly")] {
+        let (status, _, body) = post_json("/Execute", serde_json::json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": secret_arn,
+            "database": DATABASE_MAIN,
+            "sql": "SELECT SUBSTRING_INDEX(CURRENT_USER(), '@', 1) AS user",
+        })).await;
+        assert_eq!(status, 200);
+        assert_eq!(body["records"], serde_json::json!([[{"stringValue": user}]]));
+    }
+}
+
+#[actix_rt::test]
+async fn secrets_statement_denied_to_its_user_is_bad_request() {
+    create_readonly_user().await;
+    let (status, error_type, body) = post_json("/Execute", serde_json::json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": READONLY_SECRET_ARN,
+        "database": DATABASE_MAIN,
+        "sql": "CREATE TABLE data_api_denied (id INT)",
+    })).await;
+    assert_eq!(status, 400);
+    assert_eq!(error_type, "BadRequestException");
+    assert!(body["message"].as_str().unwrap().starts_with("CREATE command denied to user 'data_api_readonly'"));
+}
+
+#[actix_rt::test]
+async fn secrets_wrong_password_is_bad_request() {
+    let (status, error_type, body) = post_json("/Execute", serde_json::json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": WRONG_PASSWORD_SECRET_ARN,
+        "sql": "SELECT 1",
+    })).await;
+    assert_eq!(status, 400);
+    assert_eq!(error_type, "BadRequestException");
+    assert_eq!(body["__type"], "BadRequestException");
+    assert!(body["message"].as_str().unwrap().starts_with("Access denied for user 'root'"));
+}
+
+#[actix_rt::test]
+async fn secrets_cannot_use_the_transaction_of_another_secret() {
+    let (status, _, body) = post_json("/BeginTransaction", serde_json::json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_MAIN,
+    })).await;
+    assert_eq!(status, 200);
+    let transaction_id = body["transactionId"].as_str().unwrap().to_owned();
+    let expected_message = format!("Transaction {} was not started with the secret {}", transaction_id, READONLY_SECRET_ARN);
+
+    for (path, request) in [
+        ("/Execute", serde_json::json!({"resourceArn": RESOURCE_ARN, "secretArn": READONLY_SECRET_ARN, "transactionId": transaction_id, "sql": "SELECT 1"})),
+        ("/BatchExecute", serde_json::json!({"resourceArn": RESOURCE_ARN, "secretArn": READONLY_SECRET_ARN, "transactionId": transaction_id, "sql": "SELECT 1", "parameterSets": []})),
+        ("/CommitTransaction", serde_json::json!({"resourceArn": RESOURCE_ARN, "secretArn": READONLY_SECRET_ARN, "transactionId": transaction_id})),
+        ("/RollbackTransaction", serde_json::json!({"resourceArn": RESOURCE_ARN, "secretArn": READONLY_SECRET_ARN, "transactionId": transaction_id})),
+    ] {
+        let (status, error_type, body) = post_json(path, request).await;
+        assert_eq!(status, 400, "{}", path);
+        assert_eq!(error_type, "BadRequestException");
+        assert_eq!(body["message"], expected_message.as_str());
+    }
+
+    // the transaction is still open for the secret that started it
+    let (status, _, body) = post_json("/RollbackTransaction", serde_json::json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "transactionId": transaction_id,
+    })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["transactionStatus"], "Rollback Complete");
+}
@@ -0,0 +1,51 @@
+extern crate reqwest;
+use std::time::Duration;
+use serde_json::{json, Value};
+
+mod common;
+
+use common::{Emulator, RESOURCE_ARN, SECRET_ARN};
+
+// any engine pauses the same way, sqlite needs no database server
+fn start_emulator() -> Emulator {
+    Emulator::start(&[("ENGINE", "sqlite"), ("AUTO_PAUSE_SECONDS", "2"), ("RESUME_SECONDS", "2")])
+}
+
+async fn execute(emulator: &Emulator, transaction_id: Option<&str>) -> (u16, Value) {
+    emulator.post("/Execute", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id, "sql": "SELECT 1"})).await
+}
+
+fn assert_resuming((status, body): (u16, Value)) {
+    assert_eq!(status, 400);
+    assert_eq!(body["__type"], "DatabaseResumingException");
+    assert_eq!(body["message"], format!("The Aurora DB instance {} is resuming after being auto-paused. Please wait a few seconds and try again.", RESOURCE_ARN));
+}
+
+#[actix_rt::test]
+async fn cluster_with_open_transaction_does_not_pause() {
+    let emulator = start_emulator();
+    let (status, body) = emulator.post("/BeginTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN})).await;
+    assert_eq!(status, 200);
+    let transaction_id = body["transactionId"].as_str().unwrap().to_owned();
+
+    actix_rt::time::delay_for(Duration::from_millis(2500)).await;
+    assert_eq!(execute(&emulator, Some(&transaction_id)).await.0, 200);
+    actix_rt::time::delay_for(Duration::from_millis(2500)).await;
+    let (status, _) = emulator.post("/CommitTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id})).await;
+    assert_eq!(status, 200);
+
+    // the commit was activity too
+    assert_eq!(execute(&emulator, None).await.0, 200);
+}
+
+#[actix_rt::test]
+async fn idle_cluster_pauses_and_resumes() {
+    let emulator = start_emulator();
+    assert_eq!(execute(&emulator, None).await.0, 200);
+    actix_rt::time::delay_for(Duration::from_millis(2500)).await;
+    assert_resuming(execute(&emulator, None).await);
+    assert_resuming(execute(&emulator, None).await);
+
+    actix_rt::time::delay_for(Duration::from_millis(2500)).await;
+    assert_eq!(execute(&emulator, None).await.0, 200);
+}
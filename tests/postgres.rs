@@ -0,0 +1,137 @@
+extern crate reqwest;
+use serde_json::{json, Value};
+
+mod common;
+
+use common::{Emulator, RESOURCE_ARN, SECRET_ARN};
+
+const DATABASE_TEST: &str = "postgres";
+const SCHEMA_TEST: &str = "leliam_data_api";
+
+// the steps build on each other in one schema, they need the postgres of docker-compose
+#[actix_rt::test]
+#[ignore]
+async fn doc_table() {
+    let emulator = Emulator::start(&[("ENGINE", "postgres")]);
+    step_1_create_fresh_schema(&emulator).await;
+    step_2_perform_transaction(&emulator).await;
+    step_3_arrays(&emulator).await;
+    step_4_special_numerics(&emulator).await;
+}
+
+async fn step_1_create_fresh_schema(emulator: &Emulator) {
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": format!("DROP SCHEMA IF EXISTS {} CASCADE", SCHEMA_TEST),
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[],\"columnMetadata\":[]}");
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": format!("CREATE SCHEMA {}", SCHEMA_TEST),
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[],\"columnMetadata\":[]}");
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "schema": SCHEMA_TEST,
+        "sql": "CREATE TABLE doc (key varchar(255) PRIMARY KEY, price numeric(10, 2) NULL, quantity int NULL, created_at timestamp NOT NULL DEFAULT '2021-02-08 13:18:58')",
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[],\"columnMetadata\":[]}");
+}
+
+async fn step_2_perform_transaction(emulator: &Emulator) {
+    let body = emulator.post_text("/BeginTransaction", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "schema": SCHEMA_TEST,
+    })).await;
+    let transaction: Value = serde_json::from_str(&body).unwrap();
+    let transaction_id = transaction["transactionId"].as_str().unwrap().to_owned();
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "sql": "INSERT INTO doc (key, price, quantity) VALUES (:key, :price::numeric, :quantity)",
+        "parameters": [
+            {"name": "key", "value": {"stringValue": "doc_a"}},
+            {"name": "price", "value": {"stringValue": "12.50"}},
+            {"name": "quantity", "value": {"isNull": true}},
+        ],
+        "transactionId": transaction_id,
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":1,\"records\":[],\"columnMetadata\":[]}");
+
+    let body = emulator.post_text("/CommitTransaction", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "transactionId": transaction_id,
+    })).await;
+    assert_eq!(body, "{\"transactionStatus\":\"Transaction Committed\"}");
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "schema": SCHEMA_TEST,
+        "sql": "SELECT key, price, quantity, created_at FROM doc WHERE key = :key",
+        "parameters": [
+            {"name": "key", "value": {"stringValue": "doc_a"}},
+        ],
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"stringValue\":\"doc_a\"},{\"stringValue\":\"12.50\"},{\"isNull\":true},{\"stringValue\":\"2021-02-08 13:18:58\"}]],\"columnMetadata\":[]}");
+}
+
+async fn step_3_arrays(emulator: &Emulator) {
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": "SELECT ARRAY[1, 2, 3]::int8[] AS ids, ARRAY['a', 'b'] AS tags",
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"arrayValue\":{\"longValues\":[1,2,3]}},{\"arrayValue\":{\"stringValues\":[\"a\",\"b\"]}}]],\"columnMetadata\":[]}");
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": "SELECT ARRAY[[1, 2], [3, 4]] AS matrix, ARRAY[]::int[] AS empty, ARRAY[1, NULL] AS holes",
+    })).await;
+    assert_eq!(body, "{\"__type\":\"UnsupportedResultException\",\"message\":\"not implemented conversion of array with null elements\"}");
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": "SELECT ARRAY[[1, 2], [3, 4]] AS matrix, ARRAY[[['a']], [['b']]] AS cube, ARRAY[]::int[] AS empty",
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"arrayValue\":{\"arrayValues\":[{\"longValues\":[1,2]},{\"longValues\":[3,4]}]}},{\"arrayValue\":{\"arrayValues\":[{\"arrayValues\":[{\"stringValues\":[\"a\"]}]},{\"arrayValues\":[{\"stringValues\":[\"b\"]}]}]}},{\"arrayValue\":{\"longValues\":[]}}]],\"columnMetadata\":[]}");
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": "SELECT :ids AS ids",
+        "parameters": [
+            {"name": "ids", "value": {"arrayValue": {"longValues": [1, 2, 3]}}},
+        ],
+    })).await;
+    assert_eq!(body, "{\"__type\":\"BadRequestException\",\"message\":\"Array parameters are not supported.\"}");
+}
+
+async fn step_4_special_numerics(emulator: &Emulator) {
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": "SELECT 'NaN'::numeric, 'Infinity'::numeric, '-Infinity'::numeric, '-0.0050'::numeric",
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"stringValue\":\"NaN\"},{\"stringValue\":\"Infinity\"},{\"stringValue\":\"-Infinity\"},{\"stringValue\":\"-0.0050\"}]],\"columnMetadata\":[]}");
+}
@@ -0,0 +1,157 @@
+extern crate reqwest;
+use serde_json::{json, Value};
+
+mod common;
+
+use common::{Emulator, RESOURCE_ARN, SECRET_ARN};
+
+const DATABASE_TEST: &str = "leliam_data_api";
+
+// the steps build on each other in one database
+#[actix_rt::test]
+async fn doc_table() {
+    let emulator = Emulator::start(&[("ENGINE", "sqlite")]);
+    step_1_create_fresh_table(&emulator).await;
+    step_2_perform_transaction(&emulator).await;
+    step_3_generated_fields(&emulator).await;
+}
+
+async fn step_1_create_fresh_table(emulator: &Emulator) {
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": "DROP TABLE IF EXISTS doc",
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[],\"columnMetadata\":[]}");
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": "CREATE TABLE doc (`id` INTEGER PRIMARY KEY AUTOINCREMENT, `key` varchar(255) NOT NULL UNIQUE, `content` text NULL, `published` BOOLEAN NOT NULL DEFAULT 0)",
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[],\"columnMetadata\":[]}");
+}
+
+async fn step_2_perform_transaction(emulator: &Emulator) {
+    let body = emulator.post_text("/BeginTransaction", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+    })).await;
+    let transaction: Value = serde_json::from_str(&body).unwrap();
+    let transaction_id = transaction["transactionId"].as_str().unwrap().to_owned();
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "sql": "INSERT INTO `doc` (`key`, `content`, `published`) VALUES (:key, :contentValue, :published) ON CONFLICT(`key`) DO UPDATE SET content = :contentValue",
+        "parameters": [
+            {"name": "extra_key", "value": {"stringValue": "doc_b"}},
+            {"name": "key", "value": {"stringValue": "doc_a"}},
+            {"name": "contentValue", "value": {"stringValue": "somecontentvalue"}},
+            {"name": "published", "value": {"booleanValue": true}},
+        ],
+        "transactionId": transaction_id,
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":1,\"generatedFields\":[{\"longValue\":1}],\"records\":[],\"columnMetadata\":[]}");
+
+    let body = emulator.post_text("/CommitTransaction", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "transactionId": transaction_id,
+    })).await;
+    assert_eq!(body, "{\"transactionStatus\":\"Transaction Committed\"}");
+
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "sql": "SELECT `key`, `content`, `published` FROM `doc` WHERE `key` = :key",
+        "parameters": [
+            {"name": "key", "value": {"stringValue": "doc_a"}},
+        ],
+    })).await;
+    assert_eq!(body, "{\"numberOfRecordsUpdated\":0,\"records\":[[{\"stringValue\":\"doc_a\"},{\"stringValue\":\"somecontentvalue\"},{\"booleanValue\":true}]],\"columnMetadata\":[]}");
+}
+
+async fn step_3_generated_fields(emulator: &Emulator) {
+    // one connection for all of them, last_insert_rowid is per connection
+    let body = emulator.post_text("/BeginTransaction", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+    })).await;
+    let transaction: Value = serde_json::from_str(&body).unwrap();
+    let transaction_id = transaction["transactionId"].as_str().unwrap().to_owned();
+    let statements = [
+        ("INSERT INTO `doc` (`key`) VALUES ('doc_b')", "{\"numberOfRecordsUpdated\":1,\"generatedFields\":[{\"longValue\":2}],\"records\":[],\"columnMetadata\":[]}"),
+        ("UPDATE `doc` SET `content` = 'updated'", "{\"numberOfRecordsUpdated\":2,\"records\":[],\"columnMetadata\":[]}"),
+        ("DELETE FROM `doc` WHERE `key` = 'doc_b'", "{\"numberOfRecordsUpdated\":1,\"records\":[],\"columnMetadata\":[]}"),
+        // the same rowid again is still a new row
+        ("INSERT INTO `doc` (`id`, `key`) VALUES (2, 'doc_b')", "{\"numberOfRecordsUpdated\":1,\"generatedFields\":[{\"longValue\":2}],\"records\":[],\"columnMetadata\":[]}"),
+        ("INSERT OR IGNORE INTO `doc` (`key`) VALUES ('doc_b')", "{\"numberOfRecordsUpdated\":0,\"records\":[],\"columnMetadata\":[]}"),
+    ];
+    for (sql, expected) in statements.iter() {
+        let body = emulator.post_text("/Execute", json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "sql": sql,
+            "transactionId": transaction_id,
+        })).await;
+        assert_eq!(body, *expected, "{}", sql);
+    }
+    emulator.post_text("/CommitTransaction", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "transactionId": transaction_id,
+    })).await;
+}
+
+#[actix_rt::test]
+async fn database_name_and_schema_are_validated() {
+    let emulator = Emulator::start(&[("ENGINE", "sqlite")]);
+    for database in ["", "../escape", "a/b", "a\\b", "a\u{0}b", ".."] {
+        let body = emulator.post_text("/Execute", json!({
+            "resourceArn": RESOURCE_ARN,
+            "secretArn": SECRET_ARN,
+            "database": database,
+            "sql": "SELECT 1",
+        })).await;
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["__type"], "BadRequestException");
+        assert_eq!(body["message"], format!("Invalid database name '{}', it has to be a file name without '/', '\\' or '..'", database));
+    }
+
+    let schema_error = "{\"__type\":\"BadRequestException\",\"message\":\"The schema parameter isn't supported for SQLite, use database instead\"}";
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+        "schema": "main",
+        "sql": "SELECT 1",
+    })).await;
+    assert_eq!(body, schema_error);
+
+    let body = emulator.post_text("/BeginTransaction", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "database": DATABASE_TEST,
+    })).await;
+    let transaction: Value = serde_json::from_str(&body).unwrap();
+    let transaction_id = transaction["transactionId"].as_str().unwrap().to_owned();
+    let body = emulator.post_text("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "schema": "main",
+        "sql": "SELECT 1",
+        "transactionId": transaction_id,
+    })).await;
+    assert_eq!(body, schema_error);
+    emulator.post_text("/RollbackTransaction", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "transactionId": transaction_id,
+    })).await;
+}
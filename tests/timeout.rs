@@ -0,0 +1,162 @@
+extern crate reqwest;
+use std::time::{Duration, Instant};
+use serde_json::{json, Value};
+
+mod common;
+
+use common::{Emulator, RESOURCE_ARN, SECRET_ARN};
+
+// SLEEP needs the mysql of docker-compose
+fn start_mysql_emulator() -> Emulator {
+    Emulator::start(&[("ENGINE", "mysql"), ("STATEMENT_TIMEOUT", "2")])
+}
+
+// pg_sleep needs the postgres of docker-compose
+fn start_postgres_emulator() -> Emulator {
+    Emulator::start(&[("ENGINE", "postgres"), ("STATEMENT_TIMEOUT", "2")])
+}
+
+fn start_sqlite_emulator() -> Emulator {
+    Emulator::start(&[("ENGINE", "sqlite"), ("STATEMENT_TIMEOUT", "1")])
+}
+
+async fn begin_transaction(emulator: &Emulator) -> String {
+    let (status, body) = emulator.post("/BeginTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN})).await;
+    assert_eq!(status, 200);
+    body["transactionId"].as_str().unwrap().to_owned()
+}
+
+async fn execute(emulator: &Emulator, transaction_id: Option<&str>, sql: &str, continue_after_timeout: bool) -> (u16, Value) {
+    emulator.post("/Execute", json!({
+        "resourceArn": RESOURCE_ARN,
+        "secretArn": SECRET_ARN,
+        "transactionId": transaction_id,
+        "sql": sql,
+        "continueAfterTimeout": continue_after_timeout,
+    })).await
+}
+
+async fn rollback_transaction(emulator: &Emulator, transaction_id: &str) {
+    let (status, _) = emulator.post("/RollbackTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id})).await;
+    assert_eq!(status, 200);
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn timed_out_statement_is_cancelled() {
+    let emulator = start_mysql_emulator();
+    let transaction_id = begin_transaction(&emulator).await;
+    let started_at = Instant::now();
+    let (status, body) = execute(&emulator, Some(&transaction_id), "SELECT SLEEP(5)", false).await;
+    assert_eq!(status, 400);
+    assert_eq!(body, json!({"__type": "StatementTimeoutException", "message": "Request timed out"}));
+    assert!(started_at.elapsed() >= Duration::from_secs(2));
+
+    // KILL QUERY ended the sleep, the transaction is free right away
+    let (status, body) = execute(&emulator, Some(&transaction_id), "SELECT 1", false).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["records"], json!([[{"longValue": 1}]]));
+    assert!(started_at.elapsed() < Duration::from_secs(4));
+    rollback_transaction(&emulator, &transaction_id).await;
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn continue_after_timeout_statement_keeps_running() {
+    let emulator = start_mysql_emulator();
+    let transaction_id = begin_transaction(&emulator).await;
+    let other_transaction_id = begin_transaction(&emulator).await;
+    let started_at = Instant::now();
+    let (status, body) = execute(&emulator, Some(&transaction_id), "SELECT SLEEP(3)", true).await;
+    assert_eq!(status, 400);
+    assert_eq!(body, json!({"__type": "StatementTimeoutException", "message": "Request timed out"}));
+
+    // other transactions and statements do not wait for it
+    let (status, _) = execute(&emulator, Some(&other_transaction_id), "SELECT 1", false).await;
+    assert_eq!(status, 200);
+    let (status, _) = execute(&emulator, None, "SELECT 1", false).await;
+    assert_eq!(status, 200);
+    assert!(started_at.elapsed() < Duration::from_secs(3));
+
+    // the next statement of its transaction runs once the sleep is over
+    let (status, _) = execute(&emulator, Some(&transaction_id), "SELECT 1", false).await;
+    assert_eq!(status, 200);
+    assert!(started_at.elapsed() >= Duration::from_secs(3));
+    rollback_transaction(&emulator, &transaction_id).await;
+    rollback_transaction(&emulator, &other_transaction_id).await;
+}
+
+#[actix_rt::test]
+async fn waiting_for_a_statement_still_running_is_not_a_timeout() {
+    let emulator = start_sqlite_emulator();
+    let (status, _) = execute(&emulator, None, "CREATE TABLE doc (key TEXT)", false).await;
+    assert_eq!(status, 200);
+    // an open write of another transaction keeps the insert waiting on the sqlite file lock
+    let locking_transaction_id = begin_transaction(&emulator).await;
+    let (status, _) = execute(&emulator, Some(&locking_transaction_id), "INSERT INTO doc VALUES ('doc_b')", false).await;
+    assert_eq!(status, 200);
+
+    let transaction_id = begin_transaction(&emulator).await;
+    let (status, body) = execute(&emulator, Some(&transaction_id), "INSERT INTO doc VALUES ('doc_a')", true).await;
+    assert_eq!(status, 400);
+    assert_eq!(body, json!({"__type": "StatementTimeoutException", "message": "Request timed out"}));
+
+    // the count waits for the insert longer than the timeout, without timing out or cancelling the insert
+    let started_at = Instant::now();
+    let ((status, body), _) = futures::future::join(
+        execute(&emulator, Some(&transaction_id), "SELECT COUNT(*) FROM doc", false),
+        async {
+            actix_rt::time::delay_for(Duration::from_secs(2)).await;
+            let (status, _) = emulator.post("/CommitTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": locking_transaction_id})).await;
+            assert_eq!(status, 200);
+        },
+    ).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["records"], json!([[{"longValue": 2}]]));
+    assert!(started_at.elapsed() >= Duration::from_secs(2));
+
+    let (status, _) = emulator.post("/CommitTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id})).await;
+    assert_eq!(status, 200);
+    let (_, body) = execute(&emulator, None, "SELECT key FROM doc ORDER BY key", false).await;
+    assert_eq!(body["records"], json!([[{"stringValue": "doc_a"}], [{"stringValue": "doc_b"}]]));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn waiting_for_a_postgres_statement_still_running_is_not_a_timeout() {
+    let emulator = start_postgres_emulator();
+    let transaction_id = begin_transaction(&emulator).await;
+    let (status, _) = execute(&emulator, Some(&transaction_id), "CREATE TEMPORARY TABLE doc (key TEXT)", false).await;
+    assert_eq!(status, 200);
+    let (status, body) = execute(&emulator, Some(&transaction_id), "INSERT INTO doc SELECT 'doc_a' FROM pg_sleep(5)", true).await;
+    assert_eq!(status, 400);
+    assert_eq!(body, json!({"__type": "StatementTimeoutException", "message": "Request timed out"}));
+
+    // waits 3 seconds for the insert, longer than its own timeout, and does not cancel it
+    let (status, body) = execute(&emulator, Some(&transaction_id), "SELECT COUNT(*) FROM doc", false).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["records"], json!([[{"longValue": 1}]]));
+    rollback_transaction(&emulator, &transaction_id).await;
+}
+
+#[actix_rt::test]
+async fn interrupted_sqlite_write_ends_its_transaction() {
+    let emulator = start_sqlite_emulator();
+    let (status, _) = execute(&emulator, None, "CREATE TABLE doc (key TEXT)", false).await;
+    assert_eq!(status, 200);
+    let transaction_id = begin_transaction(&emulator).await;
+    let (status, _) = execute(&emulator, Some(&transaction_id), "INSERT INTO doc VALUES ('doc_a')", false).await;
+    assert_eq!(status, 200);
+
+    // counting takes far longer than the timeout, the interrupt rolls the whole transaction back
+    let sql = "INSERT INTO doc SELECT COUNT(*) FROM (WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 1000000000) SELECT x FROM c)";
+    let (status, body) = execute(&emulator, Some(&transaction_id), sql, false).await;
+    assert_eq!(status, 400);
+    assert_eq!(body, json!({"__type": "StatementTimeoutException", "message": "Request timed out"}));
+
+    let not_found = json!({"__type": "TransactionNotFoundException", "message": format!("Transaction {} is not found", transaction_id)});
+    assert_eq!(execute(&emulator, Some(&transaction_id), "INSERT INTO doc VALUES ('doc_b')", false).await, (404, not_found.clone()));
+    assert_eq!(emulator.post("/RollbackTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "transactionId": transaction_id})).await, (404, not_found));
+    let (_, body) = execute(&emulator, None, "SELECT COUNT(*) FROM doc", false).await;
+    assert_eq!(body["records"], json!([[{"longValue": 0}]]));
+}
@@ -0,0 +1,29 @@
+extern crate reqwest;
+use serde_json::json;
+
+mod common;
+
+use common::{Emulator, RESOURCE_ARN, SECRET_ARN};
+
+#[actix_rt::test]
+async fn unreachable_database_is_unavailable() {
+    // nothing listens on the port of the database
+    let emulator = Emulator::start(&[("ENGINE", "mysql"), ("MYSQL_HOST", "localhost"), ("MYSQL_PORT", "3399")]);
+    let client = reqwest::Client::new();
+    for (path, body) in [
+        ("/Execute", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "sql": "SELECT 1"})),
+        ("/BeginTransaction", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN})),
+        ("/BatchExecute", json!({"resourceArn": RESOURCE_ARN, "secretArn": SECRET_ARN, "sql": "SELECT 1", "parameterSets": []})),
+    ] {
+        let response = client.post(&emulator.url(path))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 504);
+        assert_eq!(response.headers().get("x-amzn-ErrorType").unwrap(), "DatabaseUnavailableException");
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["__type"], "DatabaseUnavailableException");
+        assert!(body["message"].as_str().unwrap().starts_with("Database is unavailable: "));
+    }
+}